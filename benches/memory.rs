@@ -0,0 +1,42 @@
+use std::thread;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use derohe::pow::astrobwt::{sha3, Pow16Context};
+use derohe::pow::backend::Backend;
+use derohe::pow::memory::MemoryOptions;
+
+const OPTIONS: MemoryOptions = MemoryOptions { huge_pages: true, lock: false, numa: false };
+
+/// Contexts built on one thread carve their scratch out of that thread's huge page.
+fn shared(count: usize) -> Vec<Pow16Context> {
+    (0..count).map(|_| Pow16Context::with_memory(Backend::default(), OPTIONS)).collect()
+}
+
+/// Each context built on its own thread, so each maps a page of its own.
+fn separate(count: usize) -> Vec<Pow16Context> {
+    (0..count)
+        .map(|_| thread::spawn(|| Pow16Context::with_memory(Backend::default(), OPTIONS)).join().unwrap())
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let input = sha3(b"memory");
+    // per hash cost is the reported time divided by the number of contexts
+    let mut group = c.benchmark_group("huge_pages");
+    for count in [1usize, 2, 4, 8] {
+        group.throughput(Throughput::Elements(count as u64));
+        for (name, mut contexts) in [("shared", shared(count)), ("separate", separate(count))] {
+            group.bench_with_input(BenchmarkId::new(format!("{} {:?}", name, contexts[0].placement().pages), count), &input, |b, input| {
+                b.iter(|| {
+                    for context in contexts.iter_mut() {
+                        black_box(context.hash(black_box(input)));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -2,6 +2,9 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use derohe::pow::{astrobwt, salsa20};
 use divsufsort::sort_in_place;
 use derohe::pow::astrobwt::{sha3, STAGE1_LENGTH};
+use derohe::pow::backend::Backend;
+use derohe::pow::memory::MemoryOptions;
+use derohe::pow::suffix_array::{self, SuffixSorter};
 use criterion_cycles_per_byte::CyclesPerByte;
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -16,9 +19,21 @@ fn criterion_benchmark(c: &mut Criterion) {
     );
     let mut csa = vec![0; stage1_result.len()];
     let mut sa = vec![0; stage1_result.len()];
+    let mut rsa = vec![0u16; stage1_result.len()];
+    let mut sorter = SuffixSorter::new();
+    let mut context = astrobwt::Pow16Context::new();
     c.bench_function("pow16", |b| b.iter(|| astrobwt::pow16(black_box(&input))));
+    c.bench_function("pow16_context", |b| b.iter(|| context.hash(black_box(&input))));
+    let memory = MemoryOptions { huge_pages: true, lock: true, numa: true };
+    let mut pinned = astrobwt::Pow16Context::with_memory(Backend::default(), memory);
+    c.bench_function(&format!("pow16_context_pinned {:?}", pinned.placement()), |b| b.iter(|| pinned.hash(black_box(&input))));
+    let mut stream = [0u8; STAGE1_LENGTH];
+    c.bench_function("salsa20_xor", |b| b.iter(|| salsa20::xor_key_stream(black_box(&mut stream), &[0u8; STAGE1_LENGTH], black_box(&key))));
+    c.bench_function("salsa20_key_stream", |b| b.iter(|| salsa20::key_stream(black_box(&mut stream), black_box(&key))));
     c.bench_function("csa", |b| b.iter(|| cdivsufsort::sort_in_place(black_box(&stage1_result), black_box(&mut csa))));
     c.bench_function("sa", |b| b.iter(|| divsufsort::sort_in_place(black_box(&stage1_result), black_box(&mut sa))));
+    c.bench_function("rsa", |b| b.iter(|| suffix_array::sort_in_place(black_box(&stage1_result), black_box(&mut rsa))));
+    c.bench_function("rsa_reused", |b| b.iter(|| sorter.sort(black_box(&stage1_result), black_box(&mut rsa))));
 }
 
 fn criterion_benchmark_cycles(c: &mut Criterion<CyclesPerByte>) {
@@ -33,9 +48,21 @@ fn criterion_benchmark_cycles(c: &mut Criterion<CyclesPerByte>) {
     );
     let mut csa = vec![0; stage1_result.len()];
     let mut sa = vec![0; stage1_result.len()];
+    let mut rsa = vec![0u16; stage1_result.len()];
+    let mut sorter = SuffixSorter::new();
+    let mut context = astrobwt::Pow16Context::new();
     c.bench_function("pow16", |b| b.iter(|| astrobwt::pow16(black_box(&input))));
+    c.bench_function("pow16_context", |b| b.iter(|| context.hash(black_box(&input))));
+    let memory = MemoryOptions { huge_pages: true, lock: true, numa: true };
+    let mut pinned = astrobwt::Pow16Context::with_memory(Backend::default(), memory);
+    c.bench_function(&format!("pow16_context_pinned {:?}", pinned.placement()), |b| b.iter(|| pinned.hash(black_box(&input))));
+    let mut stream = [0u8; STAGE1_LENGTH];
+    c.bench_function("salsa20_xor", |b| b.iter(|| salsa20::xor_key_stream(black_box(&mut stream), &[0u8; STAGE1_LENGTH], black_box(&key))));
+    c.bench_function("salsa20_key_stream", |b| b.iter(|| salsa20::key_stream(black_box(&mut stream), black_box(&key))));
     c.bench_function("csa", |b| b.iter(|| cdivsufsort::sort_in_place(black_box(&stage1_result), black_box(&mut csa))));
     c.bench_function("sa", |b| b.iter(|| divsufsort::sort_in_place(black_box(&stage1_result), black_box(&mut sa))));
+    c.bench_function("rsa", |b| b.iter(|| suffix_array::sort_in_place(black_box(&stage1_result), black_box(&mut rsa))));
+    c.bench_function("rsa_reused", |b| b.iter(|| sorter.sort(black_box(&stage1_result), black_box(&mut rsa))));
 }
 
 criterion_group!(benches1, criterion_benchmark);
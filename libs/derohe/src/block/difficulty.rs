@@ -0,0 +1,55 @@
+use num_bigint::{BigUint, ToBigUint};
+
+/// Go's `blockchain.CheckPowHash`: the hash, read little endian, must not exceed `ConvertDifficultyToBig`, that is
+/// 2^256 / difficulty rounded down. Go panics on a zero difficulty, nothing meets it here.
+pub fn check_pow_hash(pow_hash: [u8; 32], difficulty: u64) -> bool {
+    if difficulty == 0 {
+        return false;
+    }
+    let big_pow_hash = BigUint::from_bytes_le(&pow_hash);
+    let big_difficulty = (BigUint::from(1u8) << 256) / difficulty.to_biguint().unwrap();
+    big_pow_hash <= big_difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::check_pow_hash;
+
+    #[rstest]
+    #[case([0xff; 32], 1, true)]
+    #[case([0xff; 32], 2, false)]
+    #[case([0x00; 32], u64::MAX, true)]
+    #[case([0x00; 32], 0, false)]
+    fn pow_hash(#[case] hash: [u8; 32], #[case] difficulty: u64, #[case] expected: bool) {
+        assert_eq!(check_pow_hash(hash, difficulty), expected);
+    }
+
+    // the largest hash Go's `CheckPowHash` accepts, 2^256 / difficulty as `big.Int.Div` rounds it, and the next one
+    #[rstest]
+    #[case(3, "5555555555555555555555555555555555555555555555555555555555555555",
+           "5655555555555555555555555555555555555555555555555555555555555555")]
+    #[case(1000, "a7c64b37894160e5d022dbf97e6abc74931804560e2db29defa7c64b37894100",
+           "a8c64b37894160e5d022dbf97e6abc74931804560e2db29defa7c64b37894100")]
+    #[case(60_000_000, "82e0ba2b849e96e8f5f02ee02ec4b3cd5627306614946bd3a19c319547000000",
+           "83e0ba2b849e96e8f5f02ee02ec4b3cd5627306614946bd3a19c319547000000")]
+    #[case(u64::MAX, "0100000000000000010000000000000001000000000000000100000000000000",
+           "0200000000000000010000000000000001000000000000000100000000000000")]
+    fn pow_hash_boundary(#[case] difficulty: u64, #[case] largest: &str, #[case] next: &str) {
+        let hash = |hex: &str| -> [u8; 32] { base16::decode(hex).unwrap().try_into().unwrap() };
+        assert!(check_pow_hash(hash(largest), difficulty));
+        assert!(!check_pow_hash(hash(next), difficulty));
+    }
+
+    #[test]
+    fn pow_hash_byte_order() {
+        let mut hash = [0u8; 32];
+        // 2^255 + 1 is just above 2^256 / 2 once read little endian
+        hash[31] = 0x80;
+        hash[0] = 0x01;
+        assert!(!check_pow_hash(hash, 2));
+        hash[0] = 0x00;
+        assert!(check_pow_hash(hash, 2));
+    }
+}
@@ -1,3 +1,5 @@
+mod difficulty;
 mod miniblock;
 
-pub use miniblock::MINIBLOCK_SIZE;
\ No newline at end of file
+pub use difficulty::check_pow_hash;
+pub use miniblock::MINIBLOCK_SIZE;
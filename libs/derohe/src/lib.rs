@@ -1,6 +1,16 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "std")]
 #[macro_use] extern crate serde_derive;
+#[cfg(feature = "std")]
 extern crate serde;
+#[cfg(feature = "std")]
 pub mod rpc;
+#[cfg(feature = "std")]
+pub mod transaction;
+#[cfg(feature = "std")]
+pub mod wallet;
 pub mod block;
 pub mod pow;
 
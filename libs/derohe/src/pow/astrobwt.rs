@@ -1,27 +1,75 @@
-use std::convert::TryInto;
+use alloc::boxed::Box;
 
 use sha3::{Digest, Sha3_256};
 
+use super::backend::{Backend, SuffixSort};
+use super::memory::{MemoryOptions, Placement, Scratch};
 use super::salsa20;
 
 pub const STAGE1_LENGTH: usize = 9973;
 
+/// Hashes with a throwaway context, miners should keep a `Pow16Context` per thread instead.
 pub fn pow16(input: &[u8]) -> [u8; 32] {
-    let mut key = sha3(&input); // Step 1: calculate SHA3 of input data
-    let mut stage1_result = [0u8; STAGE1_LENGTH];
-
-    salsa20::xor_key_stream(
-        &mut stage1_result,
-        &[0u8; STAGE1_LENGTH],
-        &key,
-    );
-    let mut sa = vec![0; stage1_result.len()];
-    // divsufsort::sort_in_place(&stage1_result, &mut sa);
-    cdivsufsort::sort_in_place(&stage1_result, &mut sa);
-    let val: Vec<u16> = sa.iter().map(|&val| val as u16).collect();
-    let bb = unsafe { val.align_to::<u8>().1 };
-    let key = sha3(bb);
-    key.into()
+    Pow16Context::new().hash(input)
+}
+
+/// Scratch buffers for `pow16`, allocated once so hashing in a loop doesn't touch the allocator.
+/// The suffix array, its little endian bytes and stage 1 share one scratch.
+#[derive(Debug)]
+pub struct Pow16Context {
+    scratch: Scratch,
+    backend: Backend,
+    sorter: Box<dyn SuffixSort>,
+}
+
+const SCRATCH_LENGTH: usize = 2 * STAGE1_LENGTH + 2 * STAGE1_LENGTH + STAGE1_LENGTH;
+
+impl Pow16Context {
+    pub fn new() -> Self {
+        Self::with_backend(Backend::default())
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        Self::with_memory(backend, MemoryOptions::default())
+    }
+
+    /// Allocates scratch with `memory`, check `placement` for what the OS granted.
+    pub fn with_memory(backend: Backend, memory: MemoryOptions) -> Self {
+        Pow16Context {
+            scratch: Scratch::new(SCRATCH_LENGTH, memory),
+            backend,
+            sorter: backend.sorter(memory),
+        }
+    }
+
+    pub fn placement(&self) -> Placement {
+        self.scratch.placement()
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn hash(&mut self, input: &[u8]) -> [u8; 32] {
+        let key = sha3(input); // Step 1: calculate SHA3 of input data
+        let (sa, rest) = self.scratch.as_mut_bytes().split_at_mut(2 * STAGE1_LENGTH);
+        let (sa_bytes, stage1) = rest.split_at_mut(2 * STAGE1_LENGTH);
+        // scratch is 8 byte aligned and the suffix array sits at its start
+        let sa = unsafe { core::slice::from_raw_parts_mut(sa.as_mut_ptr() as *mut u16, STAGE1_LENGTH) };
+        salsa20::key_stream(stage1, &key);
+        self.sorter.sort(stage1, sa);
+        // serialized little endian, the byte order the reference implementation hashes
+        for (bytes, index) in sa_bytes.chunks_exact_mut(2).zip(sa.iter()) {
+            bytes.copy_from_slice(&index.to_le_bytes());
+        }
+        sha3(sa_bytes)
+    }
+}
+
+impl Default for Pow16Context {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub fn sha3(input: &[u8]) -> [u8; 32] {
@@ -38,9 +86,9 @@ mod tests {
     use rstest::rstest;
     use crate::pow::astrobwt::pow16;
 
-    use super::{sha3, STAGE1_LENGTH};
-    use super::salsa20;
-    use bn::arith;
+    use super::{sha3, Pow16Context};
+    use crate::pow::backend::Backend;
+    use crate::pow::memory::MemoryOptions;
 
     #[rstest]
     fn test_pow16() {
@@ -50,4 +98,21 @@ mod tests {
         let value = num_bigint::BigInt::from_bytes_le(num_bigint::Sign::Plus, &result);
         println!("{}", value);
     }
-}
\ No newline at end of file
+
+    #[rstest]
+    fn test_context_memory() {
+        let memory = MemoryOptions { huge_pages: true, lock: true, numa: true };
+        let mut context = Pow16Context::with_memory(Backend::default(), memory);
+        let input = sha3(b"memory");
+        assert_eq!(context.hash(&input), pow16(&input));
+    }
+
+    #[rstest]
+    fn test_context_reuse() {
+        let mut context = Pow16Context::new();
+        for i in 0u32..8 {
+            let input = sha3(&i.to_le_bytes());
+            assert_eq!(context.hash(&input), pow16(&input), "input {}", i);
+        }
+    }
+}
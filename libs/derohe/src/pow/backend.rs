@@ -0,0 +1,211 @@
+//! Interchangeable implementations of the AstroBWT suffix sorting stage.
+//!
+//! The pure-Rust sorter is always built, the divsufsort ports are behind Cargo features of the same name.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::error;
+
+use super::astrobwt::Pow16Context;
+use super::memory::MemoryOptions;
+use super::suffix_array::SuffixSorter;
+
+/// Inputs with their expected `pow16` from the DERO reference implementation. Vectors computed by this crate
+/// can't catch a backend that is wrong the same way, so only reference output belongs here.
+const KNOWN_VECTORS: [(&str, &str); 1] = [
+    ("415a9e0000008386b3fe9a18000000004c2d828f0583a86db9639d36548f817100000000deb3465e1d316f0000000201",
+     "8150f739f0614744423dac0638a9fcea80cad934584bafa6a5e199a5aa2d7a3d"),
+];
+
+/// Sorts the suffixes of the AstroBWT stage 1 buffer.
+pub trait SuffixSort: Send + fmt::Debug {
+    /// Store the lexicographic order of the suffixes of `text` in `sa`.
+    fn sort(&mut self, text: &[u8], sa: &mut [u16]);
+}
+
+impl SuffixSort for SuffixSorter {
+    fn sort(&mut self, text: &[u8], sa: &mut [u16]) {
+        SuffixSorter::sort(self, text, sa)
+    }
+}
+
+/// The Rust port of divsufsort.
+#[cfg(feature = "divsufsort")]
+#[derive(Debug, Default)]
+pub struct Divsufsort {
+    sa: Vec<i32>,
+}
+
+#[cfg(feature = "divsufsort")]
+impl SuffixSort for Divsufsort {
+    fn sort(&mut self, text: &[u8], sa: &mut [u16]) {
+        self.sa.resize(text.len(), 0);
+        divsufsort::sort_in_place(text, &mut self.sa);
+        for (v, &i) in sa.iter_mut().zip(self.sa.iter()) {
+            *v = i as u16;
+        }
+    }
+}
+
+/// Bindings to the C divsufsort.
+#[cfg(feature = "cdivsufsort")]
+#[derive(Debug, Default)]
+pub struct CDivsufsort {
+    sa: Vec<i32>,
+}
+
+#[cfg(feature = "cdivsufsort")]
+impl SuffixSort for CDivsufsort {
+    fn sort(&mut self, text: &[u8], sa: &mut [u16]) {
+        self.sa.resize(text.len(), 0);
+        cdivsufsort::sort_in_place(text, &mut self.sa);
+        for (v, &i) in sa.iter_mut().zip(self.sa.iter()) {
+            *v = i as u16;
+        }
+    }
+}
+
+/// Suffix sorters compiled into this build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Backend {
+    #[default]
+    Native,
+    #[cfg(feature = "divsufsort")]
+    Divsufsort,
+    #[cfg(feature = "cdivsufsort")]
+    CDivsufsort,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[
+        Backend::Native,
+        #[cfg(feature = "divsufsort")]
+        Backend::Divsufsort,
+        #[cfg(feature = "cdivsufsort")]
+        Backend::CDivsufsort,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Native => "native",
+            #[cfg(feature = "divsufsort")]
+            Backend::Divsufsort => "divsufsort",
+            #[cfg(feature = "cdivsufsort")]
+            Backend::CDivsufsort => "cdivsufsort",
+        }
+    }
+
+    /// A sorter whose own buffers are allocated with `memory`, the divsufsort ports allocate on the heap.
+    pub fn sorter(self, memory: MemoryOptions) -> Box<dyn SuffixSort> {
+        match self {
+            Backend::Native => Box::new(SuffixSorter::with_memory(memory)),
+            #[cfg(feature = "divsufsort")]
+            Backend::Divsufsort => Box::new(Divsufsort::default()),
+            #[cfg(feature = "cdivsufsort")]
+            Backend::CDivsufsort => Box::new(CDivsufsort::default()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = UnknownBackend;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Backend::ALL.iter().copied().find(|backend| backend.name() == s).ok_or_else(|| UnknownBackend(s.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownBackend(pub String);
+
+impl fmt::Display for UnknownBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Backend::ALL.iter().map(|backend| backend.name()).collect();
+        write!(f, "unknown pow backend {}, available: {}", self.0, names.join(", "))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for UnknownBackend {}
+
+/// A backend hashed a known vector to the wrong value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestError {
+    pub backend: Backend,
+    pub input: Vec<u8>,
+    pub expected: [u8; 32],
+    pub actual: [u8; 32],
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pow backend {} failed self-test, input={} expected={} actual={}",
+               self.backend, base16::encode_lower(&self.input), base16::encode_lower(&self.expected), base16::encode_lower(&self.actual))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for SelfTestError {}
+
+/// Hashes the known vectors with `backend`, mining with a backend that fails would only produce rejected work.
+pub fn self_test(backend: Backend) -> Result<(), SelfTestError> {
+    let mut context = Pow16Context::with_backend(backend);
+    for (input, expected) in KNOWN_VECTORS.iter() {
+        let input = base16::decode(input).unwrap();
+        let mut expected_hash = [0u8; 32];
+        expected_hash.copy_from_slice(&base16::decode(expected).unwrap());
+        let actual = context.hash(&input);
+        if actual != expected_hash {
+            return Err(SelfTestError { backend, input, expected: expected_hash, actual });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{self_test, Backend};
+    use crate::pow::astrobwt::{self, STAGE1_LENGTH};
+    use crate::pow::memory::MemoryOptions;
+    use crate::pow::salsa20;
+
+    #[rstest]
+    fn all_backends_pass_self_test() {
+        for &backend in Backend::ALL {
+            assert_eq!(self_test(backend), Ok(()), "{}", backend);
+        }
+    }
+
+    #[rstest]
+    fn backends_agree() {
+        let mut stage1 = [0u8; STAGE1_LENGTH];
+        salsa20::key_stream(&mut stage1, &astrobwt::sha3(b"backends"));
+        let mut expected = [0u16; STAGE1_LENGTH];
+        Backend::Native.sorter(MemoryOptions::default()).sort(&stage1, &mut expected);
+        for &backend in Backend::ALL {
+            let mut sa = [0u16; STAGE1_LENGTH];
+            backend.sorter(MemoryOptions::default()).sort(&stage1, &mut sa);
+            assert!(sa == expected, "{}", backend);
+        }
+    }
+
+    #[rstest]
+    fn parse_names() {
+        for &backend in Backend::ALL {
+            assert_eq!(backend.name().parse::<Backend>(), Ok(backend));
+        }
+        assert!("sais".parse::<Backend>().is_err());
+    }
+}
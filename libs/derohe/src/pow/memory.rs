@@ -0,0 +1,345 @@
+//! Scratch memory for hashing contexts, optionally on huge pages, locked in RAM and bound to the local NUMA node.
+//!
+//! Each option falls back on its own when the OS refuses it, `Placement` tells what was granted.
+//! Huge page scratch smaller than a page is carved out of one page per thread, so a context's buffers share it.
+//! Outside Linux the scratch always comes from the heap.
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+/// What a context asks for when allocating scratch, the default is plain heap memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MemoryOptions {
+    /// Back the scratch with 2 MB pages, explicit ones from the hugetlb pool or else transparent ones.
+    pub huge_pages: bool,
+    /// `mlock` the scratch so it never gets swapped out.
+    pub lock: bool,
+    /// Bind the scratch to the NUMA node of the allocating thread.
+    pub numa: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Pages {
+    #[default]
+    Heap,
+    /// An anonymous mapping on regular pages.
+    Normal,
+    /// Regular pages the kernel was advised to merge into huge ones.
+    Transparent,
+    /// Pages from the reserved hugetlb pool.
+    Explicit,
+}
+
+/// What the OS granted for a scratch buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub pages: Pages,
+    pub locked: bool,
+    pub numa_node: Option<u32>,
+}
+
+impl Placement {
+    /// Whether every requested option was granted.
+    pub fn satisfies(&self, options: MemoryOptions) -> bool {
+        (!options.huge_pages || matches!(self.pages, Pages::Transparent | Pages::Explicit))
+            && (!options.lock || self.locked)
+            && (!options.numa || self.numa_node.is_some())
+    }
+}
+
+/// A zeroed byte buffer aligned to 8 bytes.
+#[derive(Debug)]
+pub struct Scratch {
+    memory: Memory,
+    len: usize,
+    placement: Placement,
+}
+
+#[derive(Debug)]
+enum Memory {
+    Heap(Box<[u64]>),
+    #[cfg(target_os = "linux")]
+    Mapped { ptr: core::ptr::NonNull<u8>, size: usize },
+    /// Part of a thread's huge page, the page is unmapped with its last part.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    Shared { ptr: core::ptr::NonNull<u8>, page: core::ptr::NonNull<linux::shared::Page> },
+}
+
+// the mapping is owned by the scratch alone like the boxed variant, a shared page counts its parts atomically
+unsafe impl Send for Scratch {}
+
+impl Scratch {
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    pub fn new(len: usize, options: MemoryOptions) -> Self {
+        #[cfg(target_os = "linux")]
+        if options != MemoryOptions::default() {
+            if let Some(scratch) = linux::allocate(len, options) {
+                return scratch;
+            }
+        }
+        Scratch {
+            memory: Memory::Heap(vec![0u64; len.div_ceil(8)].into_boxed_slice()),
+            len,
+            placement: Placement::default(),
+        }
+    }
+
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        match &mut self.memory {
+            Memory::Heap(words) => unsafe { core::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, self.len) },
+            #[cfg(target_os = "linux")]
+            Memory::Mapped { ptr, .. } => unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), self.len) },
+            #[cfg(all(target_os = "linux", feature = "std"))]
+            Memory::Shared { ptr, .. } => unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), self.len) },
+        }
+    }
+
+    /// The buffer as `len / 8` words.
+    pub fn as_mut_words(&mut self) -> &mut [u64] {
+        let bytes = self.as_mut_bytes();
+        // every variant is 8 byte aligned
+        unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr() as *mut u64, bytes.len() / 8) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        match self.memory {
+            Memory::Heap(_) => (),
+            Memory::Mapped { ptr, size } => unsafe {
+                libc::munmap(ptr.as_ptr() as *mut libc::c_void, size);
+            },
+            #[cfg(feature = "std")]
+            Memory::Shared { page, .. } => unsafe { linux::shared::release(page) },
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use core::ptr::{self, NonNull};
+
+    use libc::{c_ulong, c_void};
+
+    use super::{Memory, MemoryOptions, Pages, Placement, Scratch};
+
+    const PAGE_SIZE: usize = 4096;
+    const HUGE_PAGE_SIZE: usize = 2 << 20;
+    /// From linux/mempolicy.h, libc doesn't export it.
+    const MPOL_BIND: libc::c_int = 2;
+
+    fn round_up(len: usize, to: usize) -> usize {
+        len.max(1).div_ceil(to) * to
+    }
+
+    unsafe fn mmap(size: usize, flags: libc::c_int) -> Option<*mut c_void> {
+        let ptr = libc::mmap(ptr::null_mut(), size, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags, -1, 0);
+        if ptr == libc::MAP_FAILED { None } else { Some(ptr) }
+    }
+
+    /// Allocates scratch with the requested options, `None` only when no mapping could be made at all.
+    pub(super) fn allocate(len: usize, options: MemoryOptions) -> Option<Scratch> {
+        #[cfg(feature = "std")]
+        if options.huge_pages && len <= HUGE_PAGE_SIZE {
+            if let Some(scratch) = shared::carve(len, options) {
+                return Some(scratch);
+            }
+        }
+        unsafe {
+            let mut placement = Placement::default();
+            let (ptr, size) = if options.huge_pages {
+                let size = round_up(len, HUGE_PAGE_SIZE);
+                (map_huge(size, &mut placement)?, size)
+            } else {
+                let size = round_up(len, PAGE_SIZE);
+                placement.pages = Pages::Normal;
+                (mmap(size, 0)?, size)
+            };
+            place(ptr, size, options, &mut placement);
+            Some(Scratch {
+                memory: Memory::Mapped { ptr: NonNull::new(ptr as *mut u8)?, size },
+                len,
+                placement,
+            })
+        }
+    }
+
+    /// Maps `size` bytes, a multiple of `HUGE_PAGE_SIZE`, on explicit huge pages or else transparent ones.
+    unsafe fn map_huge(size: usize, placement: &mut Placement) -> Option<*mut c_void> {
+        if let Some(ptr) = mmap(size, libc::MAP_HUGETLB) {
+            placement.pages = Pages::Explicit;
+            return Some(ptr);
+        }
+        // transparent huge pages only back 2 MB aligned ranges, map extra and trim to alignment
+        let padded = mmap(size + HUGE_PAGE_SIZE, 0)?;
+        let start = round_up(padded as usize, HUGE_PAGE_SIZE);
+        let head = start - padded as usize;
+        if head > 0 {
+            libc::munmap(padded, head);
+        }
+        libc::munmap((start + size) as *mut c_void, HUGE_PAGE_SIZE - head);
+        let ptr = start as *mut c_void;
+        placement.pages = if libc::madvise(ptr, size, libc::MADV_HUGEPAGE) == 0 { Pages::Transparent } else { Pages::Normal };
+        Some(ptr)
+    }
+
+    /// Binds and locks a fresh mapping as `options` ask.
+    unsafe fn place(ptr: *mut c_void, size: usize, options: MemoryOptions, placement: &mut Placement) {
+        // bind before anything touches the pages, locking faults them in
+        if options.numa {
+            let (mut cpu, mut node) = (0u32, 0u32);
+            let found = libc::syscall(libc::SYS_getcpu, &mut cpu as *mut u32, &mut node as *mut u32, ptr::null_mut::<c_void>()) == 0;
+            if found && node < c_ulong::BITS {
+                let mask: c_ulong = 1 << node;
+                let bound = libc::syscall(libc::SYS_mbind, ptr, size, MPOL_BIND, &mask as *const c_ulong, c_ulong::BITS as c_ulong + 1, 0) == 0;
+                if bound {
+                    placement.numa_node = Some(node);
+                }
+            }
+        }
+        if options.lock {
+            placement.locked = libc::mlock(ptr, size) == 0;
+        }
+    }
+
+    /// One huge page per thread that scratch smaller than a page is carved from.
+    #[cfg(feature = "std")]
+    pub(super) mod shared {
+        use core::cell::RefCell;
+        use core::ptr::NonNull;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use alloc::boxed::Box;
+        use libc::c_void;
+
+        use super::super::{Memory, MemoryOptions, Placement, Scratch};
+        use super::HUGE_PAGE_SIZE;
+
+        /// Parts start on their own cache line.
+        const ALIGN: usize = 64;
+
+        /// A mapped huge page and how many parts of it are alive, the thread carving it holds one more.
+        pub struct Page {
+            ptr: NonNull<u8>,
+            options: MemoryOptions,
+            placement: Placement,
+            refs: AtomicUsize,
+        }
+
+        /// The page this thread carves from and how much of it is handed out.
+        struct Current {
+            page: NonNull<Page>,
+            used: usize,
+        }
+
+        impl Drop for Current {
+            fn drop(&mut self) {
+                unsafe { release(self.page) }
+            }
+        }
+
+        std::thread_local! {
+            static CURRENT: RefCell<Option<Current>> = const { RefCell::new(None) };
+        }
+
+        /// Hands out `len` bytes of this thread's page, mapping a new one when it is full or was placed with other options.
+        /// Nothing is carved twice, so parts come out zeroed like a fresh mapping.
+        pub(in super::super) fn carve(len: usize, options: MemoryOptions) -> Option<Scratch> {
+            CURRENT.try_with(|current| {
+                let mut current = current.borrow_mut();
+                let fits = |c: &Current| unsafe { c.page.as_ref() }.options == options && c.used.next_multiple_of(ALIGN) + len <= HUGE_PAGE_SIZE;
+                if !current.as_ref().is_some_and(fits) {
+                    *current = Some(Current { page: Page::map(options)?, used: 0 });
+                }
+                let current = current.as_mut()?;
+                let page = unsafe { current.page.as_ref() };
+                let offset = current.used.next_multiple_of(ALIGN);
+                current.used = offset + len;
+                page.refs.fetch_add(1, Ordering::Relaxed);
+                Some(Scratch {
+                    memory: Memory::Shared { ptr: unsafe { NonNull::new_unchecked(page.ptr.as_ptr().add(offset)) }, page: current.page },
+                    len,
+                    placement: page.placement,
+                })
+            }).ok().flatten()
+        }
+
+        /// Drops one reference to `page`, unmapping it with the last.
+        pub(in super::super) unsafe fn release(page: NonNull<Page>) {
+            if page.as_ref().refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+                let page = Box::from_raw(page.as_ptr());
+                libc::munmap(page.ptr.as_ptr() as *mut c_void, HUGE_PAGE_SIZE);
+            }
+        }
+
+        impl Page {
+            fn map(options: MemoryOptions) -> Option<NonNull<Page>> {
+                unsafe {
+                    let mut placement = Placement::default();
+                    let ptr = super::map_huge(HUGE_PAGE_SIZE, &mut placement)?;
+                    super::place(ptr, HUGE_PAGE_SIZE, options, &mut placement);
+                    let page = Page { ptr: NonNull::new(ptr as *mut u8)?, options, placement, refs: AtomicUsize::new(1) };
+                    Some(NonNull::from(Box::leak(Box::new(page))))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{MemoryOptions, Pages, Placement, Scratch};
+
+    #[rstest]
+    #[case(MemoryOptions::default())]
+    #[case(MemoryOptions { huge_pages: true, lock: false, numa: false })]
+    #[case(MemoryOptions { huge_pages: false, lock: true, numa: true })]
+    #[case(MemoryOptions { huge_pages: true, lock: true, numa: true })]
+    fn usable_whatever_is_granted(#[case] options: MemoryOptions) {
+        let mut scratch = Scratch::new(50_000, options);
+        let bytes = scratch.as_mut_bytes();
+        assert_eq!(bytes.len(), 50_000);
+        assert_eq!(bytes.as_ptr() as usize % 8, 0);
+        assert!(bytes.iter().all(|&b| b == 0));
+        bytes.fill(0xa5);
+        if options == MemoryOptions::default() {
+            assert_eq!(scratch.placement(), Placement::default());
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[rstest]
+    fn huge_scratch_shares_a_page() {
+        let options = MemoryOptions { huge_pages: true, lock: false, numa: false };
+        let mut first = Scratch::new(1000, options);
+        let mut second = Scratch::new(1000, options);
+        if first.placement().pages == Pages::Normal {
+            // no huge pages at all on this machine
+            return;
+        }
+        let first_start = first.as_mut_bytes().as_ptr() as usize;
+        let second = std::thread::spawn(move || {
+            let bytes = second.as_mut_bytes();
+            assert!(bytes.iter().all(|&b| b == 0));
+            bytes.as_ptr() as usize
+        }).join().unwrap();
+        assert_eq!(second - first_start, 1024);
+        first.as_mut_bytes().fill(0xa5);
+    }
+
+    #[rstest]
+    fn satisfies() {
+        let all = MemoryOptions { huge_pages: true, lock: true, numa: true };
+        assert!(Placement::default().satisfies(MemoryOptions::default()));
+        assert!(!Placement::default().satisfies(all));
+        assert!(Placement { pages: Pages::Transparent, locked: true, numa_node: Some(0) }.satisfies(all));
+        assert!(!Placement { pages: Pages::Normal, locked: true, numa_node: Some(0) }.satisfies(all));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod astrobwt;
-pub mod salsa20;
\ No newline at end of file
+pub mod backend;
+pub mod memory;
+pub mod salsa20;
+pub mod suffix_array;
\ No newline at end of file
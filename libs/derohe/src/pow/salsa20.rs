@@ -35,6 +35,155 @@ pub fn xor_key_stream(mut output: &mut [u8], mut input: &[u8], key: &[u8; KEY_SI
     }
 }
 
+/// CPU features `key_stream` detects at runtime, it uses them whatever the build targets.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+pub const RUNTIME_FEATURES: &[&str] = &["sse2", "avx2"];
+#[cfg(not(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64"))))]
+pub const RUNTIME_FEATURES: &[&str] = &[];
+
+/// Fills `output` with the keystream `xor_key_stream` would XOR against, using the widest SIMD the CPU has.
+pub fn key_stream(output: &mut [u8], key: &[u8; KEY_SIZE]) {
+    #[allow(unused_mut)]
+    let mut blocks = 0;
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if x86::has_avx2() {
+            blocks = unsafe { x86::key_stream_avx2(output, key) };
+        } else if x86::has_sse2() {
+            blocks = unsafe { x86::key_stream_sse2(output, key) };
+        }
+    }
+    key_stream_scalar(&mut output[blocks * BLOCK_SIZE..], key, blocks as u64);
+}
+
+/// Writes the keystream one block at a time, starting from block `counter`.
+fn key_stream_scalar(output: &mut [u8], key: &[u8; KEY_SIZE], mut counter: u64) {
+    let mut block = [0u8; BLOCK_SIZE];
+    let mut input = [0u8; COUNTER_SIZE];
+    for chunk in output.chunks_mut(BLOCK_SIZE) {
+        input[8..].copy_from_slice(&counter.to_le_bytes());
+        core(&mut block, &input, key, &SIGMA);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        counter += 1;
+    }
+}
+
+/// Salsa20 input words for `key`, a zero nonce and block 0.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn initial_state(key: &[u8; KEY_SIZE]) -> [u32; 16] {
+    let word = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    [
+        word(&SIGMA[0..]), word(&key[0..]), word(&key[4..]), word(&key[8..]),
+        word(&key[12..]), word(&SIGMA[4..]), 0, 0,
+        0, 0, word(&SIGMA[8..]), word(&key[16..]),
+        word(&key[20..]), word(&key[24..]), word(&key[28..]), word(&SIGMA[12..]),
+    ]
+}
+
+/// Computes several blocks at once, each vector lane holds the same state word of a different block.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use super::{initial_state, BLOCK_SIZE, KEY_SIZE, ROUNDS};
+
+    #[cfg(feature = "std")]
+    pub fn has_avx2() -> bool {
+        is_x86_feature_detected!("avx2")
+    }
+
+    #[cfg(feature = "std")]
+    pub fn has_sse2() -> bool {
+        is_x86_feature_detected!("sse2")
+    }
+
+    /// Runtime detection needs std, without it only what the build targets is used.
+    #[cfg(not(feature = "std"))]
+    pub fn has_avx2() -> bool {
+        cfg!(target_feature = "avx2")
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn has_sse2() -> bool {
+        cfg!(target_feature = "sse2")
+    }
+
+    macro_rules! key_stream_lanes {
+        ($name:ident, $feature:literal, $lanes:literal, $vec:ty, $set1:ident, $loadu:ident, $storeu:ident,
+         $add:ident, $xor:ident, $or:ident, $slli:ident, $srli:ident) => {
+            /// Fills as many whole groups of blocks as fit, returns how many blocks were written.
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $name(output: &mut [u8], key: &[u8; KEY_SIZE]) -> usize {
+                macro_rules! quarter {
+                    ($x:ident, $a:literal, $b:literal, $c:literal, $d:literal) => {
+                        let u = $add($x[$a], $x[$d]);
+                        $x[$b] = $xor($x[$b], $or($slli(u, 7), $srli(u, 25)));
+                        let u = $add($x[$b], $x[$a]);
+                        $x[$c] = $xor($x[$c], $or($slli(u, 9), $srli(u, 23)));
+                        let u = $add($x[$c], $x[$b]);
+                        $x[$d] = $xor($x[$d], $or($slli(u, 13), $srli(u, 19)));
+                        let u = $add($x[$d], $x[$c]);
+                        $x[$a] = $xor($x[$a], $or($slli(u, 18), $srli(u, 14)));
+                    };
+                }
+
+                let state = initial_state(key);
+                let mut initial = [$set1(0); 16];
+                for (v, &word) in initial.iter_mut().zip(state.iter()) {
+                    *v = $set1(word as i32);
+                }
+                let mut counter = 0u64;
+                let mut chunks = output.chunks_exact_mut($lanes * BLOCK_SIZE);
+                for chunk in &mut chunks {
+                    let mut low = [0u32; $lanes];
+                    let mut high = [0u32; $lanes];
+                    for lane in 0..$lanes {
+                        let block = counter + lane as u64;
+                        low[lane] = block as u32;
+                        high[lane] = (block >> 32) as u32;
+                    }
+                    let mut j = initial;
+                    j[8] = $loadu(low.as_ptr() as *const $vec);
+                    j[9] = $loadu(high.as_ptr() as *const $vec);
+
+                    let mut x = j;
+                    for _ in (0..ROUNDS).step_by(2) {
+                        quarter!(x, 0, 4, 8, 12);
+                        quarter!(x, 5, 9, 13, 1);
+                        quarter!(x, 10, 14, 2, 6);
+                        quarter!(x, 15, 3, 7, 11);
+
+                        quarter!(x, 0, 1, 2, 3);
+                        quarter!(x, 5, 6, 7, 4);
+                        quarter!(x, 10, 11, 8, 9);
+                        quarter!(x, 15, 12, 13, 14);
+                    }
+
+                    let mut words = [[0u32; $lanes]; 16];
+                    for w in 0..16 {
+                        $storeu(words[w].as_mut_ptr() as *mut $vec, $add(x[w], j[w]));
+                    }
+                    for (lane, block) in chunk.chunks_exact_mut(BLOCK_SIZE).enumerate() {
+                        for (w, bytes) in block.chunks_exact_mut(4).enumerate() {
+                            bytes.copy_from_slice(&words[w][lane].to_le_bytes());
+                        }
+                    }
+                    counter += $lanes;
+                }
+                counter as usize
+            }
+        };
+    }
+
+    key_stream_lanes!(key_stream_sse2, "sse2", 4, __m128i, _mm_set1_epi32, _mm_loadu_si128, _mm_storeu_si128,
+        _mm_add_epi32, _mm_xor_si128, _mm_or_si128, _mm_slli_epi32, _mm_srli_epi32);
+    key_stream_lanes!(key_stream_avx2, "avx2", 8, __m256i, _mm256_set1_epi32, _mm256_loadu_si256, _mm256_storeu_si256,
+        _mm256_add_epi32, _mm256_xor_si256, _mm256_or_si256, _mm256_slli_epi32, _mm256_srli_epi32);
+}
+
 fn core(
     output: &mut [u8; BLOCK_SIZE],
     input: &[u8; COUNTER_SIZE],
@@ -234,3 +383,66 @@ fn core(
     output[62] = (x15 >> 16) as u8;
     output[63] = (x15 >> 24) as u8;
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{key_stream, key_stream_scalar, xor_key_stream};
+
+    fn reference(key: &[u8; 32], length: usize) -> Vec<u8> {
+        let mut output = vec![0u8; length];
+        xor_key_stream(&mut output, &vec![0u8; length], key);
+        output
+    }
+
+    // eSTREAM Salsa20/20 256 bit key vectors, zero IV
+    #[rstest]
+    #[case("8000000000000000000000000000000000000000000000000000000000000000", 0, "E3BE8FDD8BECA2E3EA8EF9475B29A6E7003951E1097A5C38D23B7A5FAD9F6844B22C97559E2723C7CBBD3FE4FC8D9A0744652A83E72A9C461876AF4D7EF1A117")]
+    #[case("8000000000000000000000000000000000000000000000000000000000000000", 192, "57BE81F47B17D9AE7C4FF15429A73E10ACF250ED3A90A93C711308A74C6216A9ED84CD126DA7F28E8ABF8BB63517E1CA98E712F4FB2E1A6AED9FDC73291FAA17")]
+    #[case("0000000000000000000000000000000000000000000000000000000000000000", 0, "9A97F65B9B4C721B960A672145FCA8D4E32E67F9111EA979CE9C4826806AEEE63DE9C0DA2BD7F91EBCB2639BF989C6251B29BF38D39A9BDCE7C55F4B2AC12A39")]
+    fn estream_vectors(#[case] key: &str, #[case] offset: usize, #[case] expected: &str) {
+        let mut k = [0u8; 32];
+        k.copy_from_slice(&base16::decode(key).unwrap());
+        let expected = base16::decode(expected).unwrap();
+        let mut output = [0u8; 512];
+        key_stream(&mut output, &k);
+        assert_eq!(&output[offset..offset + 64], &expected[..]);
+        assert_eq!(reference(&k, 512)[offset..offset + 64], expected[..]);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(64)]
+    #[case(255)]
+    #[case(513)]
+    #[case(9973)]
+    fn matches_scalar(#[case] length: usize) {
+        let key = crate::pow::astrobwt::sha3(&length.to_le_bytes());
+        let expected = reference(&key, length);
+        let mut output = vec![0u8; length];
+        key_stream(&mut output, &key);
+        assert_eq!(output, expected);
+        key_stream_scalar(&mut output, &key, 0);
+        assert_eq!(output, expected);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[rstest]
+    fn simd_matches_scalar() {
+        let key = crate::pow::astrobwt::sha3(b"simd");
+        let expected = reference(&key, 9973);
+        let mut output = vec![0u8; 9973];
+        if is_x86_feature_detected!("sse2") {
+            let blocks = unsafe { super::x86::key_stream_sse2(&mut output, &key) };
+            assert_eq!(blocks, 9973 / 64 / 4 * 4);
+            assert_eq!(output[..blocks * 64], expected[..blocks * 64]);
+        }
+        if is_x86_feature_detected!("avx2") {
+            let blocks = unsafe { super::x86::key_stream_avx2(&mut output, &key) };
+            assert_eq!(blocks, 9973 / 64 / 8 * 8);
+            assert_eq!(output[..blocks * 64], expected[..blocks * 64]);
+        }
+    }
+}
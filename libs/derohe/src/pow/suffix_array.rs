@@ -0,0 +1,358 @@
+//! Suffix array construction writing `u16` indices, tuned for the AstroBWT stage 1 buffer.
+//!
+//! Stage 1 is Salsa20 output, so suffixes almost always differ within their first few bytes. They are
+//! radix sorted by a short prefix and the rare ties compared directly. Inputs with too many ties fall
+//! back to SA-IS, ported from the AtCoder Library `sa_is`. Either way the result is the plain
+//! lexicographic suffix array, identical to what divsufsort produces.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::pow::memory::{MemoryOptions, Scratch};
+
+const EMPTY: u16 = u16::MAX;
+/// Below this length sorting the suffixes directly is faster than inducing.
+const NAIVE_THRESHOLD: usize = 10;
+/// Bytes of each suffix the radix pass sorts on.
+const PREFIX_LENGTH: usize = 3;
+/// Most suffixes sharing a prefix with another before SA-IS is cheaper than comparing them.
+const TIE_BUDGET: usize = 64;
+
+/// Longest text that fits, `u16::MAX` is reserved to mark empty slots.
+pub const MAX_LENGTH: usize = EMPTY as usize - 1;
+
+/// Keeps the scratch buffers of every recursion level so repeated sorts don't allocate.
+#[derive(Debug)]
+pub struct SuffixSorter {
+    /// The radix pass entries and as many again to scatter them into, `capacity` of each.
+    entries: Scratch,
+    capacity: usize,
+    memory: MemoryOptions,
+    levels: Vec<Level>,
+}
+
+impl Default for SuffixSorter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Level {
+    ls: Vec<bool>,
+    sum_l: Vec<u32>,
+    sum_s: Vec<u32>,
+    buf: Vec<u32>,
+    lms_map: Vec<u16>,
+    lms: Vec<u16>,
+    sorted_lms: Vec<u16>,
+    rec_s: Vec<u16>,
+    rec_sa: Vec<u16>,
+}
+
+impl SuffixSorter {
+    pub fn new() -> Self {
+        Self::with_memory(MemoryOptions::default())
+    }
+
+    /// Allocates the radix pass buffers with `memory`, the rare SA-IS fallback stays on the heap.
+    pub fn with_memory(memory: MemoryOptions) -> Self {
+        SuffixSorter { entries: Scratch::new(0, MemoryOptions::default()), capacity: 0, memory, levels: vec![] }
+    }
+
+    /// Sort suffixes of `text` and store their lexicographic order in `sa`.
+    /// Will panic if `sa.len()` != `text.len()` or the text is longer than `MAX_LENGTH`.
+    pub fn sort(&mut self, text: &[u8], sa: &mut [u16]) {
+        assert_eq!(text.len(), sa.len(), "text and suffix array should have same len");
+        assert!(text.len() <= MAX_LENGTH, "text too large, should not exceed {} bytes", MAX_LENGTH);
+        if !self.prefix_sort(text, sa) {
+            sa_is(text, u8::MAX as usize, sa, &mut self.levels, 0);
+        }
+    }
+
+    /// Radix sorts suffixes by their first `PREFIX_LENGTH` bytes, returns false when too many share a prefix.
+    fn prefix_sort(&mut self, text: &[u8], sa: &mut [u16]) -> bool {
+        let n = text.len();
+        // entry layout is the prefix, zero padded past the end of the text, above a 16 bit index
+        let byte = |i: usize| text.get(i).map_or(0, |&b| b as u64);
+        if self.capacity < n {
+            self.entries = Scratch::new(2 * n * mem::size_of::<u64>(), self.memory);
+            self.capacity = n;
+        }
+        // every pass scatters into all of `scratch`, it needs no clearing
+        let (mut entries, mut scratch) = self.entries.as_mut_words()[..2 * n].split_at_mut(n);
+        for (i, entry) in entries.iter_mut().enumerate() {
+            *entry = byte(i) << 32 | byte(i + 1) << 24 | byte(i + 2) << 16 | i as u64;
+        }
+
+        let mut counts = [[0u32; 256]; PREFIX_LENGTH];
+        for &entry in entries.iter() {
+            for (digit, count) in counts.iter_mut().enumerate() {
+                count[(entry >> (16 + 8 * digit)) as usize & 0xff] += 1;
+            }
+        }
+        for (digit, count) in counts.iter_mut().enumerate() {
+            let mut sum = 0;
+            for c in count.iter_mut() {
+                let value = *c;
+                *c = sum;
+                sum += value;
+            }
+            for &entry in entries.iter() {
+                let bucket = &mut count[(entry >> (16 + 8 * digit)) as usize & 0xff];
+                scratch[*bucket as usize] = entry;
+                *bucket += 1;
+            }
+            mem::swap(&mut entries, &mut scratch);
+        }
+
+        let mut ties = 0;
+        let mut start = 0;
+        while start < n {
+            let prefix = entries[start] >> 16;
+            let mut end = start + 1;
+            while end < n && entries[end] >> 16 == prefix {
+                end += 1;
+            }
+            if end - start > 1 {
+                ties += end - start;
+                if ties > TIE_BUDGET {
+                    return false;
+                }
+                entries[start..end].sort_unstable_by(|&a, &b| text[a as u16 as usize..].cmp(&text[b as u16 as usize..]));
+            }
+            start = end;
+        }
+
+        for (v, &entry) in sa.iter_mut().zip(entries.iter()) {
+            *v = entry as u16;
+        }
+        true
+    }
+}
+
+/// Sort suffixes of `text` and store their lexicographic order in `sa`.
+pub fn sort_in_place(text: &[u8], sa: &mut [u16]) {
+    SuffixSorter::new().sort(text, sa)
+}
+
+fn sa_is<T: Copy + Into<usize>>(s: &[T], upper: usize, sa: &mut [u16], levels: &mut Vec<Level>, depth: usize) {
+    let n = s.len();
+    match n {
+        0 => return,
+        1 => {
+            sa[0] = 0;
+            return;
+        }
+        2 => {
+            if s[0].into() < s[1].into() {
+                sa[0] = 0;
+                sa[1] = 1;
+            } else {
+                sa[0] = 1;
+                sa[1] = 0;
+            }
+            return;
+        }
+        _ if n < NAIVE_THRESHOLD => {
+            naive(s, sa);
+            return;
+        }
+        _ => ()
+    }
+
+    if levels.len() <= depth {
+        levels.resize_with(depth + 1, Level::default);
+    }
+    // taken out so deeper levels can still be pushed while we hold on to this one
+    let mut level = mem::take(&mut levels[depth]);
+    let Level { ls, sum_l, sum_s, buf, lms_map, lms, sorted_lms, rec_s, rec_sa } = &mut level;
+
+    ls.clear();
+    ls.resize(n, false);
+    for i in (0..n - 1).rev() {
+        let (a, b) = (s[i].into(), s[i + 1].into());
+        ls[i] = if a == b { ls[i + 1] } else { a < b };
+    }
+
+    sum_l.clear();
+    sum_l.resize(upper + 1, 0);
+    sum_s.clear();
+    sum_s.resize(upper + 1, 0);
+    for i in 0..n {
+        let c = s[i].into();
+        if !ls[i] {
+            sum_s[c] += 1;
+        } else {
+            sum_l[c + 1] += 1;
+        }
+    }
+    for i in 0..=upper {
+        sum_s[i] += sum_l[i];
+        if i < upper {
+            sum_l[i + 1] += sum_s[i];
+        }
+    }
+
+    lms_map.clear();
+    lms_map.resize(n + 1, EMPTY);
+    lms.clear();
+    for i in 1..n {
+        if !ls[i - 1] && ls[i] {
+            lms_map[i] = lms.len() as u16;
+            lms.push(i as u16);
+        }
+    }
+    let m = lms.len();
+
+    induce(s, sa, ls, sum_s, sum_l, buf, lms);
+
+    if m > 0 {
+        sorted_lms.clear();
+        sorted_lms.extend(sa.iter().copied().filter(|&v| lms_map[v as usize] != EMPTY));
+
+        rec_s.clear();
+        rec_s.resize(m, 0);
+        let mut rec_upper = 0;
+        for i in 1..m {
+            let (mut l, mut r) = (sorted_lms[i - 1] as usize, sorted_lms[i] as usize);
+            let end_l = lms.get(lms_map[l] as usize + 1).map_or(n, |&end| end as usize);
+            let end_r = lms.get(lms_map[r] as usize + 1).map_or(n, |&end| end as usize);
+            let mut same = end_l - l == end_r - r;
+            if same {
+                while l < end_l && s[l].into() == s[r].into() {
+                    l += 1;
+                    r += 1;
+                }
+                if l == n || r == n || s[l].into() != s[r].into() {
+                    same = false;
+                }
+            }
+            if !same {
+                rec_upper += 1;
+            }
+            rec_s[lms_map[sorted_lms[i] as usize] as usize] = rec_upper as u16;
+        }
+
+        rec_sa.clear();
+        rec_sa.resize(m, 0);
+        sa_is(&rec_s[..], rec_upper, &mut rec_sa[..], levels, depth + 1);
+
+        for i in 0..m {
+            sorted_lms[i] = lms[rec_sa[i] as usize];
+        }
+        induce(s, sa, ls, sum_s, sum_l, buf, sorted_lms);
+    }
+
+    levels[depth] = level;
+}
+
+fn induce<T: Copy + Into<usize>>(s: &[T], sa: &mut [u16], ls: &[bool], sum_s: &[u32], sum_l: &[u32], buf: &mut Vec<u32>, lms: &[u16]) {
+    let n = s.len();
+    sa.fill(EMPTY);
+
+    buf.clear();
+    buf.extend_from_slice(sum_s);
+    for &d in lms {
+        let c = s[d as usize].into();
+        sa[buf[c] as usize] = d;
+        buf[c] += 1;
+    }
+
+    buf.copy_from_slice(sum_l);
+    let c = s[n - 1].into();
+    sa[buf[c] as usize] = (n - 1) as u16;
+    buf[c] += 1;
+    for i in 0..n {
+        let v = sa[i];
+        if v != EMPTY && v >= 1 && !ls[v as usize - 1] {
+            let c = s[v as usize - 1].into();
+            sa[buf[c] as usize] = v - 1;
+            buf[c] += 1;
+        }
+    }
+
+    buf.copy_from_slice(sum_l);
+    for i in (0..n).rev() {
+        let v = sa[i];
+        if v != EMPTY && v >= 1 && ls[v as usize - 1] {
+            let c = s[v as usize - 1].into() + 1;
+            buf[c] -= 1;
+            sa[buf[c] as usize] = v - 1;
+        }
+    }
+}
+
+fn naive<T: Copy + Into<usize>>(s: &[T], sa: &mut [u16]) {
+    for (i, v) in sa.iter_mut().enumerate() {
+        *v = i as u16;
+    }
+    sa.sort_unstable_by(|&a, &b| {
+        let a = s[a as usize..].iter().map(|&c| c.into());
+        let b = s[b as usize..].iter().map(|&c| c.into());
+        a.cmp(b)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::pow::{astrobwt, salsa20};
+    use crate::pow::astrobwt::STAGE1_LENGTH;
+
+    use super::{sa_is, sort_in_place, SuffixSorter};
+
+    fn reference(text: &[u8]) -> Vec<u16> {
+        let mut sa = vec![0; text.len()];
+        cdivsufsort::sort_in_place(text, &mut sa);
+        sa.iter().map(|&v| v as u16).collect()
+    }
+
+    #[rstest]
+    #[case(b"".to_vec())]
+    #[case(b"a".to_vec())]
+    #[case(b"ba".to_vec())]
+    #[case(b"banana".to_vec())]
+    #[case(b"mississippi".to_vec())]
+    #[case(b"abracadabra abracadabra abracadabra".to_vec())]
+    #[case(vec![0u8; STAGE1_LENGTH])]
+    #[case(vec![0xffu8; STAGE1_LENGTH])]
+    #[case((0..STAGE1_LENGTH).map(|i| (i % 7) as u8).collect())]
+    #[case((0..STAGE1_LENGTH).map(|i| (i % 256) as u8).collect())]
+    #[case((0..STAGE1_LENGTH).rev().map(|i| (i / 40) as u8).collect())]
+    fn matches_divsufsort(#[case] text: Vec<u8>) {
+        let mut sa = vec![0; text.len()];
+        sort_in_place(&text, &mut sa);
+        assert_eq!(sa, reference(&text));
+    }
+
+    #[test]
+    fn matches_divsufsort_on_stage1() {
+        let mut sorter = SuffixSorter::new();
+        let mut stage1 = [0u8; STAGE1_LENGTH];
+        let mut sa = [0u16; STAGE1_LENGTH];
+        let mut sais = [0u16; STAGE1_LENGTH];
+        for i in 0u32..64 {
+            let key = astrobwt::sha3(&i.to_le_bytes());
+            salsa20::xor_key_stream(&mut stage1, &[0u8; STAGE1_LENGTH], &key);
+            sorter.sort(&stage1, &mut sa);
+            assert_eq!(sa.to_vec(), reference(&stage1), "key {}", i);
+            sa_is(&stage1[..], 255, &mut sais, &mut vec![], 0);
+            assert_eq!(sais, sa, "key {}", i);
+        }
+    }
+
+    #[test]
+    fn prefix_ties() {
+        // random bytes with a few repeated runs, enough ties to need comparing but not to give up on the radix pass
+        let mut stage1 = [0u8; STAGE1_LENGTH];
+        salsa20::xor_key_stream(&mut stage1, &[0u8; STAGE1_LENGTH], &astrobwt::sha3(b"ties"));
+        stage1.copy_within(100..140, 5000);
+        stage1.copy_within(100..110, STAGE1_LENGTH - 10);
+        let mut sa = [0u16; STAGE1_LENGTH];
+        sort_in_place(&stage1, &mut sa);
+        assert_eq!(sa.to_vec(), reference(&stage1));
+    }
+}
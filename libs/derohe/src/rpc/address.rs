@@ -1,127 +1,546 @@
-use std::fmt::format;
-use std::num::IntErrorKind::Empty;
+use std::{error, fmt};
+use std::hash::{Hash, Hasher};
 use std::ops::Neg;
+use std::str::FromStr;
 
 use bech32::{self, FromBase32, ToBase32, u5, Variant};
-use num_bigint::{BigInt, BigUint, Sign};
 
-use bn::{AffineG1, CurveError, Fq, G1};
-use bn::arith::U256;
+use bn::{AffineG1, CurveError, Fq, G1, Group};
 
-use crate::rpc::rpc::Arguments;
+use crate::rpc::rpc::{Arguments, ArgumentsError};
 
-#[derive(Debug, Clone, PartialEq)]
+/// Equality and hashing go by what the address encodes to, so testnet and simulator addresses, which share a
+/// prefix, are the same address and argument order doesn't matter.
+#[derive(Debug, Clone)]
 pub struct Address {
-    network: u64,
-    mainnet: bool,
+    network: Network,
     proof: bool,
-    public_key: Option<G1>,
+    public_key: G1,
     arguments: Arguments,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Simulator,
+}
+
+impl Network {
+    pub fn is_mainnet(self) -> bool {
+        self == Network::Mainnet
+    }
+
+    /// Whether an address for one network can be paid on the other, only mainnet has its own prefix.
+    pub fn is_compatible(self, other: Network) -> bool {
+        self.is_mainnet() == other.is_mainnet()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Simulator => "simulator",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Case insensitive, so the daemon's `Mainnet`/`Testnet`/`Simulator` from `GetInfo` parse too.
+impl FromStr for Network {
+    type Err = UnknownNetwork;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Network::Mainnet, Network::Testnet, Network::Simulator].into_iter()
+            .find(|network| network.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownNetwork(s.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownNetwork(pub String);
+
+impl fmt::Display for UnknownNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown network {}, expected mainnet, testnet or simulator", self.0)
+    }
+}
+
+impl error::Error for UnknownNetwork {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Plain,
+    /// Carries arguments such as a destination port for the receiving service.
+    Integrated,
+    /// Proves a payment, made by the sender's wallet.
+    Proof,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Plain => "plain",
+            Kind::Integrated => "integrated",
+            Kind::Proof => "proof",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum AddressError {
+    Bech32(bech32::Error),
+    InvalidHrp(String),
+    InvalidVersion(u8),
+    InvalidLength(usize),
+    InvalidPoint(CurveError),
+    /// The key decoded to the point at infinity, which no one holds the secret for.
+    ZeroPublicKey,
+    InvalidPayload(ArgumentsError),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Bech32(e) => write!(f, "{}", e),
+            AddressError::InvalidHrp(hrp) => write!(f, "invalid human-readable part: {}", hrp),
+            AddressError::InvalidVersion(version) => write!(f, "invalid address version: {}", version),
+            AddressError::InvalidLength(length) => write!(f, "invalid address length as per spec: {}", length),
+            AddressError::InvalidPoint(e) => write!(f, "invalid public key: {:?}", e),
+            AddressError::ZeroPublicKey => write!(f, "invalid public key: point at infinity"),
+            AddressError::InvalidPayload(e) => write!(f, "invalid integrated arguments: {}", e),
+        }
+    }
+}
+
+impl error::Error for AddressError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            AddressError::Bech32(e) => Some(e),
+            AddressError::InvalidPayload(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<bech32::Error> for AddressError {
+    fn from(e: bech32::Error) -> Self {
+        AddressError::Bech32(e)
+    }
+}
+
+/// The DERO point format: x big endian followed by a byte telling whether y is odd, 0 or 1.
+/// 33 zero bytes stand for the point at infinity, x = 0 is never on the curve since 3 isn't a square.
 pub trait ReversedG1 {
     fn from_compressed_reversed_sign(bytes: &[u8]) -> Result<G1, CurveError>;
+    fn to_compressed_reversed_sign(&self) -> [u8; 33];
 }
 
 impl ReversedG1 for G1 {
     fn from_compressed_reversed_sign(bytes: &[u8]) -> Result<Self, CurveError> {
         if bytes.len() != 33 { return Err(CurveError::InvalidEncoding); }
 
-        let sign = bytes[32];
+        let odd = match bytes[32] {
+            0 => false,
+            1 => true,
+            _ => return Err(CurveError::InvalidEncoding)
+        };
+        if bytes.iter().all(|&b| b == 0) {
+            return Ok(G1::zero());
+        }
+        // an x of p or more is the same field element as x - p, refusing it keeps one encoding per point
         let fq = Fq::from_slice(&bytes[0..32])?;
         let x = fq;
         let y_squared = (fq * fq * fq) + Self::b();
 
-        let y = y_squared.sqrt().ok_or(CurveError::NotMember)?;
-        // if sign == 2 && y.into_u256().get_bit(0).expect("bit 0 always exist; qed") { y = y.neg(); }
-        // else if sign == 3 && !y.into_u256().get_bit(0).expect("bit 0 always exist; qed") { y = y.neg(); }
-        // else if sign != 3 && sign != 2 {
-        //     return Err(CurveError::InvalidEncoding);
-        // }
-        // println!("y= {:#?}",y.into_u256());
+        let mut y = y_squared.sqrt().ok_or(CurveError::NotMember)?;
+        if is_odd(y) != odd {
+            y = y.neg();
+        }
         AffineG1::new(x, y).map_err(|_| CurveError::NotMember).map(Into::into)
     }
+
+    /// The point at infinity encodes to zeros, as in the Go wallet.
+    fn to_compressed_reversed_sign(&self) -> [u8; 33] {
+        let mut bytes = [0u8; 33];
+        if let Some(affine) = AffineG1::from_jacobian(*self) {
+            affine.x().to_big_endian(&mut bytes[..32]).expect("32 bytes hold an Fq; qed");
+            bytes[32] = is_odd(affine.y()) as u8;
+        }
+        bytes
+    }
+}
+
+fn is_odd(fq: Fq) -> bool {
+    fq.into_u256().get_bit(0).expect("bit 0 always exist; qed")
 }
 
 impl Address {
-    pub fn from_string(encoded: &str) -> Result<Self, String> {
-        let (hrp, data, variant): (String, Vec<u5>, Variant) = match bech32::decode(encoded) {
-            Ok(val_) => val_,
-            Err(e) => return Err(e.to_string())
-        };
-        let mut address = Address {
-            network: 0,
-            mainnet: false,
+    /// A plain address paying to `public_key`, `dero1…` on mainnet and `deto1…` elsewhere.
+    pub fn new(public_key: G1, network: Network) -> Self {
+        Address {
+            network,
             proof: false,
-            public_key: None,
+            public_key,
             arguments: Arguments::empty(),
-        };
+        }
+    }
+
+    /// The same address carrying `arguments`, integrated unless they're empty.
+    pub fn with_arguments(mut self, arguments: Arguments) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// The same key and arguments under the other network's prefix, proof addresses have a single prefix.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Parsed addresses only tell mainnet from the rest, `deto` ones come back as testnet.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn kind(&self) -> Kind {
+        match (self.proof, self.arguments.is_empty()) {
+            (true, _) => Kind::Proof,
+            (false, true) => Kind::Plain,
+            (false, false) => Kind::Integrated,
+        }
+    }
+
+    pub fn public_key(&self) -> G1 {
+        self.public_key
+    }
+
+    pub fn arguments(&self) -> &Arguments {
+        &self.arguments
+    }
+
+    pub fn from_string(encoded: &str) -> Result<Self, AddressError> {
+        let (hrp, data, variant): (String, Vec<u5>, Variant) = bech32::decode(encoded)?;
+        // the Go wallet only knows the original checksum, a bech32m one doesn't verify there
+        if variant != Variant::Bech32 {
+            return Err(AddressError::Bech32(bech32::Error::InvalidChecksum));
+        }
 
         match hrp.as_str() {
             "dero" | "deroi" | "deto" | "detoi" | "deroproof" => (),
-            _ => return Err("invalid human-readable part".parse().unwrap())
+            _ => return Err(AddressError::InvalidHrp(hrp))
         };
 
-        if data.len() < 1 {
-            return Err(format!("invalid decode version: {}", data.len()));
-        }
-
-        let res: Vec<u8> = Vec::<u8>::from_base32(&data).unwrap();
+        let res: Vec<u8> = Vec::<u8>::from_base32(&data)?;
 
-        if res[0] != 1 {
-            return Err(format!("invalid address version: {}", res[0]));
+        let (&version, res_bytes) = res.split_first().ok_or(AddressError::InvalidLength(0))?;
+        if version != 1 {
+            return Err(AddressError::InvalidVersion(version));
         }
-        let res_bytes = &res[1..];
         if res_bytes.len() < 33 {
-            return Err(format!("invalid address length as per spec: {}", res_bytes.len()));
+            return Err(AddressError::InvalidLength(res_bytes.len()));
         }
-        address.public_key = match G1::from_compressed_reversed_sign(&res_bytes[..33]) {
-            Ok(g) => Some(g),
-            Err(e) => return Err(format!("{:?}", e))
-        };
-        address.mainnet = match hrp.as_str() {
-            "deto" | "detoi" => false,
-            _ => true
-        };
+        let public_key = G1::from_compressed_reversed_sign(&res_bytes[..33]).map_err(AddressError::InvalidPoint)?;
+        if public_key.is_zero() {
+            return Err(AddressError::ZeroPublicKey);
+        }
+        let network = if matches!(hrp.as_str(), "deto" | "detoi") { Network::Testnet } else { Network::Mainnet };
+        let mut address = Address::new(public_key, network);
         if hrp.as_str() == "deroproof" {
             address.proof = true
         }
-        // TODO
-        // 	switch {
-        // 	case len(res) == 33 && (dechrp == "dero" || dechrp == "deto"):
-        // 	case (dechrp == "deroi" || dechrp == "detoi" || dechrp == "deroproof"): // address contains service request
-        // 		if err = result.Arguments.UnmarshalBinary(resbytes[33:]); err != nil {
-        // 			return err
-        // 		}
-        // 	default:
-        // 		return fmt.Errorf("invalid address length as per spec : %d", len(res))
-        // 	}
+        match hrp.as_str() {
+            "dero" | "deto" if res_bytes.len() == 33 => (),
+            // the rest of an integrated or proof address is the argument map, which can't be empty bytes
+            "deroi" | "detoi" | "deroproof" => {
+                address.arguments = Arguments::from_cbor(&res_bytes[33..], network).map_err(AddressError::InvalidPayload)?;
+            }
+            _ => return Err(AddressError::InvalidLength(res_bytes.len()))
+        }
 
         Ok(address)
     }
+
+    fn hrp(&self) -> &'static str {
+        match (self.proof, self.network.is_mainnet(), self.arguments.is_empty()) {
+            (true, _, _) => "deroproof",
+            (false, true, true) => "dero",
+            (false, true, false) => "deroi",
+            (false, false, true) => "deto",
+            (false, false, false) => "detoi",
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = vec![1u8];
+        bytes.extend_from_slice(&self.public_key.to_compressed_reversed_sign());
+        if self.proof || !self.arguments.is_empty() {
+            bytes.extend_from_slice(&self.arguments.to_cbor());
+        }
+        bech32::encode_to_fmt(f, self.hrp(), bytes.to_base32(), Variant::Bech32).map_err(|_| fmt::Error)?
+    }
+}
+
+impl Address {
+    fn identity(&self) -> (bool, bool, [u8; 33], Vec<u8>) {
+        (self.network.is_mainnet(), self.proof, self.public_key.to_compressed_reversed_sign(), self.arguments.to_cbor())
+    }
+}
+
+impl PartialEq for Address {
+    fn eq(&self, other: &Self) -> bool {
+        self.identity() == other.identity()
+    }
+}
+
+impl Eq for Address {}
+
+impl Hash for Address {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity().hash(state)
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Address::from_string(s)
+    }
+}
+
+impl serde::Serialize for Address {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Address {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Address::from_string(&encoded).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use bech32::{ToBase32, u5, Variant};
+    use bn::{AffineG1, Fr, G1, Group};
+    use proptest::prelude::*;
     use rstest::rstest;
 
-    use crate::rpc::address::Address;
+    use std::collections::HashSet;
+
+    use crate::rpc::address::{Address, Kind, Network, ReversedG1};
+    use crate::rpc::rpc::{Argument, Arguments, Value};
 
     #[test]
     fn valid_address() {
         let address = Address::from_string("dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmn").unwrap();
-        assert!(address.public_key.is_some());
-        assert_eq!(address.mainnet, true);
+        assert!(!address.public_key.is_zero());
+        assert_eq!(address.network(), Network::Mainnet);
+        assert_eq!(address.kind(), Kind::Plain);
+    }
+
+    fn key(seed: [u8; 32]) -> G1 {
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(&seed);
+        G1::one() * Fr::interpret(&wide)
+    }
+
+    /// y worked out apart from the bn crate, as the square root of x³ + 3 with the parity the sign byte asks for.
+    /// Not checked against the Go wallet.
+    #[rstest]
+    #[case("dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmn",
+           "0340f86a583ad3a584f8c35304bf282a52db0147d80aa46e785b502c6b8768da", "06f3dc36df77adb5418eb260105681a1ac46fc4a64b3ea446f598113f77096a8")]
+    #[case("dero1qykyta6ntpd27nl0yq4xtzaf4ls6p5e9pqu0k2x4x3pqq5xavjsdxqgny8270",
+           "2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d3", "27d6af0425f9f5ffeea1244425c992327ffde169c12be56a1462116a2f7fc9cd")]
+    // y even and above p/2, then odd and below it: reading the sign byte as "larger y" picks the other root
+    #[case("dero1qy4pguz4x7cqjxya4zqgv50wekuzfqj80l5j4sfv4zm3lq8u84y77qqtyla09",
+           "2a14705537b009189da8808651eecdb82482477fe92ac12ca8b71f80fc3d49ef", "2df7ee7f243ea8b38e1ddf14029258877a618c779fd4717db6177e19ea67ec38")]
+    #[case("dero1qy2m72a30zqpgj6arnftrarwl7wkz7ll68990smlkkjfhkzw208kvqg2wxkfh",
+           "15bf2bb17880144b5d1cd2b1f46eff9d617bffd1ca57c37fb5a49bd84e53cf66", "049c797f9ce0d17083deb32b5e36f2ea2a212ee036598dd7624c168993d1355f")]
+    fn known_public_keys(#[case] encoded: &str, #[case] x: &str, #[case] y: &str) {
+        let affine = AffineG1::from_jacobian(Address::from_string(encoded).unwrap().public_key).unwrap();
+        let mut bytes = [0u8; 32];
+        affine.x().to_big_endian(&mut bytes).unwrap();
+        assert_eq!(base16::encode_lower(&bytes), x);
+        affine.y().to_big_endian(&mut bytes).unwrap();
+        assert_eq!(base16::encode_lower(&bytes), y);
+    }
+
+    #[test]
+    fn sign_picks_the_root() {
+        let public_key = key([7; 32]);
+        let mut bytes = public_key.to_compressed_reversed_sign();
+        bytes[32] ^= 1;
+        assert_eq!(G1::from_compressed_reversed_sign(&bytes).unwrap(), -public_key);
+    }
+
+    #[test]
+    fn infinity() {
+        assert_eq!(G1::zero().to_compressed_reversed_sign(), [0; 33]);
+        assert!(G1::from_compressed_reversed_sign(&[0; 33]).unwrap().is_zero());
+    }
+
+    #[rstest]
+    // sign bytes other than 0 and 1, the prefix style of other encodings included
+    #[case("2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d302")]
+    #[case("2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d303")]
+    #[case("2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d3ff")]
+    // x = p and x = p + 1, which would alias 0 and 1
+    #[case("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd4700")]
+    #[case("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd4800")]
+    #[case("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff01")]
+    // x³ + 3 has no square root
+    #[case("000000000000000000000000000000000000000000000000000000000000000400")]
+    // infinity with a sign
+    #[case("000000000000000000000000000000000000000000000000000000000000000001")]
+    // wrong lengths
+    #[case("2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d3")]
+    #[case("2c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d30100")]
+    fn rejects_point(#[case] hex: &str) {
+        assert!(G1::from_compressed_reversed_sign(&base16::decode(hex).unwrap()).is_err());
+    }
+
+    #[test]
+    fn networks() {
+        assert_eq!("Simulator".parse(), Ok(Network::Simulator));
+        assert_eq!("mainnet".parse(), Ok(Network::Mainnet));
+        assert!("devnet".parse::<Network>().is_err());
+        assert!(Network::Testnet.is_compatible(Network::Simulator));
+        assert!(!Network::Mainnet.is_compatible(Network::Testnet));
+        let simulator = Address::new(key([1; 32]), Network::Simulator);
+        assert!(simulator.to_string().starts_with("deto1"));
+        assert_eq!(Address::from_string(&simulator.to_string()).unwrap().network(), Network::Testnet);
+        let mainnet = simulator.with_network(Network::Mainnet);
+        assert!(mainnet.to_string().starts_with("dero1"));
+        assert_eq!(mainnet.public_key(), key([1; 32]));
+    }
+
+    #[test]
+    fn equality() {
+        let testnet = Address::new(key([1; 32]), Network::Testnet);
+        let simulator = Address::new(key([1; 32]), Network::Simulator);
+        let mainnet = Address::new(key([1; 32]), Network::Mainnet);
+        assert_eq!(testnet, simulator);
+        assert_ne!(testnet, mainnet);
+        // the same arguments in another order are the same address
+        let a = Argument::new("C", Value::String("hi".into()));
+        let b = Argument::new("D", Value::Uint64(1));
+        let integrated = mainnet.clone().with_arguments(Arguments::new(vec![a.clone(), b.clone()]));
+        assert_eq!(integrated, mainnet.clone().with_arguments(Arguments::new(vec![b, a])));
+        assert_ne!(integrated, mainnet);
+        let set: HashSet<Address> = [testnet, simulator, mainnet, integrated].into_iter().collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn serde_as_string() {
+        let encoded = "\"dero1qykyta6ntpd27nl0yq4xtzaf4ls6p5e9pqu0k2x4x3pqq5xavjsdxqgny8270\"";
+        let address: Address = serde_json::from_str(encoded).unwrap();
+        assert_eq!(serde_json::to_string(&address).unwrap(), encoded);
+        assert!(serde_json::from_str::<Address>("\"dero1qqqq\"").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(seed in any::<[u8; 32]>(), mainnet in any::<bool>()) {
+            let address = Address::new(key(seed), if mainnet { Network::Mainnet } else { Network::Testnet });
+            let encoded = address.to_string();
+            let prefix = if mainnet { "dero1" } else { "deto1" };
+            prop_assert!(encoded.starts_with(prefix), "{}", encoded);
+            prop_assert_eq!(Address::from_string(&encoded).unwrap(), address);
+        }
+
+        #[test]
+        fn point_round_trip(seed in any::<[u8; 32]>()) {
+            let public_key = key(seed);
+            prop_assert_eq!(G1::from_compressed_reversed_sign(&public_key.to_compressed_reversed_sign()).unwrap(), public_key);
+        }
+    }
+
+    #[rstest]
+    // a plain address with a payload
+    #[case("dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5q9rvfp4xursv9uk7at5ypnx7u3qwf5kwgphvfz92xg98939v4g6qqpapyqwnct9p", "invalid address length as per spec: 68")]
+    // an integrated address without one
+    #[case("deroi1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqg8jqly", "invalid integrated arguments: invalid cbor at byte 0")]
+    fn length_rules(#[case] encoded: &str, #[case] error: &str) {
+        assert_eq!(Address::from_string(encoded).unwrap_err().to_string(), error);
     }
 
     #[rstest]
     #[case(String::from("der1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmn"))]
     #[case(String::from("der1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmm"))]
     fn invalid_address(#[case] address: String) {
-        let result = Address::from_string(address.as_str());
-        match result {
-            Ok(_v) => assert!(true, "{}", "shouldn't be a valid address"),
-            Err(_) => ()
+        assert!(Address::from_string(address.as_str()).is_err(), "shouldn't be a valid address");
+    }
+
+    fn encode(hrp: &str, data: Vec<u5>, variant: Variant) -> String {
+        bech32::encode(hrp, data, variant).unwrap()
+    }
+
+    fn bytes(bytes: &[u8]) -> Vec<u5> {
+        bytes.to_base32()
+    }
+
+    #[rstest]
+    #[case(encode("dero", vec![], Variant::Bech32), "invalid address length as per spec: 0")]
+    // a single 5 bit group holds no whole byte
+    #[case(encode("dero", vec![u5::try_from_u8(0).unwrap()], Variant::Bech32), "invalid padding")]
+    #[case(encode("dero", bytes(&[2; 34]), Variant::Bech32), "invalid address version: 2")]
+    #[case(encode("dero", bytes(&[1; 20]), Variant::Bech32), "invalid address length as per spec: 19")]
+    #[case(encode("monero", bytes(&[1; 34]), Variant::Bech32), "invalid human-readable part: monero")]
+    #[case(encode("dero", bytes(&[&[1][..], &[0xff; 33]].concat()), Variant::Bech32), "invalid public key: InvalidEncoding")]
+    #[case(encode("dero", bytes(&[&[1][..], &[0; 33]].concat()), Variant::Bech32), "invalid public key: point at infinity")]
+    #[case(encode("deroi", bytes(&[&[1][..], &[0; 33], &[0xa0]].concat()), Variant::Bech32), "invalid public key: point at infinity")]
+    // a valid payload under the bech32m checksum
+    #[case(encode("dero", bytes(&[&[1][..], &key([1; 32]).to_compressed_reversed_sign()].concat()), Variant::Bech32m), "invalid checksum")]
+    #[case("dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmm".to_string(), "invalid checksum")]
+    #[case("dero1".to_string(), "invalid length")]
+    fn errors(#[case] encoded: String, #[case] error: &str) {
+        assert_eq!(Address::from_string(&encoded).unwrap_err().to_string(), error);
+    }
+
+    /// Re-parsing whatever parsed gives the same address.
+    fn check_no_panic(encoded: &str) -> Result<(), TestCaseError> {
+        if let Ok(address) = Address::from_string(encoded) {
+            prop_assert_eq!(Address::from_string(&address.to_string()).unwrap(), address);
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics_on_strings(encoded in "\\PC*") {
+            check_no_panic(&encoded)?;
+        }
+
+        #[test]
+        fn never_panics_on_payloads(hrp in "dero|deroi|deto|detoi|deroproof|[a-z]{1,8}", payload in proptest::collection::vec(any::<u8>(), 0..120)) {
+            check_no_panic(&encode(&hrp, bytes(&payload), Variant::Bech32))?;
+        }
+
+        #[test]
+        fn never_panics_on_keys(hrp in "dero|deroi|deroproof", tail in proptest::collection::vec(any::<u8>(), 0..60), x in any::<[u8; 32]>(), sign in 0u8..3) {
+            let mut payload = vec![1u8];
+            payload.extend_from_slice(&x);
+            payload.push(sign);
+            payload.extend(tail);
+            check_no_panic(&encode(&hrp, bytes(&payload), Variant::Bech32))?;
+        }
+
+        #[test]
+        fn never_panics_on_groups(groups in proptest::collection::vec(0u8..32, 0..100)) {
+            let data = groups.into_iter().map(|group| u5::try_from_u8(group).unwrap()).collect();
+            check_no_panic(&encode("dero", data, Variant::Bech32))?;
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,226 @@
+//! The subset of CBOR the Go wallet writes for `rpc.Arguments`: integers, floats, byte and text strings and maps,
+//! all with definite lengths.
+
+/// One decoded data item, maps keep the order they were read in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Uint(u64),
+    /// A negative integer stored as `-1 - n`.
+    NegInt(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Map(Vec<(Item, Item)>),
+    Float(f64),
+}
+
+/// Where in the input decoding stopped making sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CborError {
+    pub offset: usize,
+}
+
+/// Nesting deeper than the argument map and its values isn't something the wallet produces.
+const MAX_DEPTH: usize = 4;
+
+/// Encodes `item` the way Go's fxamacker/cbor does by default: shortest integer heads and floats always on 8 bytes.
+/// Map entries are written in the order given.
+pub fn encode(item: &Item, out: &mut Vec<u8>) {
+    match item {
+        Item::Uint(n) => head(0, *n, out),
+        Item::NegInt(n) => head(1, *n, out),
+        Item::Bytes(bytes) => {
+            head(2, bytes.len() as u64, out);
+            out.extend_from_slice(bytes);
+        }
+        Item::Text(text) => {
+            head(3, text.len() as u64, out);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Item::Map(entries) => {
+            head(5, entries.len() as u64, out);
+            for (key, value) in entries {
+                encode(key, out);
+                encode(value, out);
+            }
+        }
+        Item::Float(f) => {
+            out.push(0xfb);
+            out.extend_from_slice(&f.to_bits().to_be_bytes());
+        }
+    }
+}
+
+fn head(major: u8, argument: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    match argument {
+        0..=23 => out.push(major | argument as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, argument as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(argument as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&argument.to_be_bytes());
+        }
+    }
+}
+
+/// Decodes exactly one item, trailing bytes are an error like in the Go decoder.
+pub fn decode(bytes: &[u8]) -> Result<Item, CborError> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let item = reader.item(0)?;
+    if reader.offset != bytes.len() {
+        return Err(CborError { offset: reader.offset });
+    }
+    Ok(item)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self) -> CborError {
+        CborError { offset: self.offset }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], CborError> {
+        let end = self.offset.checked_add(len).filter(|&end| end <= self.bytes.len()).ok_or_else(|| self.error())?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, CborError> {
+        Ok(self.take(len)?.iter().fold(0u64, |value, &b| value << 8 | b as u64))
+    }
+
+    /// The argument of a head byte, indefinite lengths and the reserved values are rejected.
+    fn argument(&mut self, info: u8) -> Result<u64, CborError> {
+        match info {
+            0..=23 => Ok(info as u64),
+            24 => self.uint(1),
+            25 => self.uint(2),
+            26 => self.uint(4),
+            27 => self.uint(8),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn length(&mut self, info: u8) -> Result<usize, CborError> {
+        let start = self.error();
+        let length = self.argument(info)?;
+        // every element takes at least a byte, anything longer than the input is a lie
+        usize::try_from(length).ok().filter(|&length| length <= self.bytes.len() - self.offset).ok_or(start)
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Item, CborError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error());
+        }
+        let start = self.error();
+        let head = self.take(1)?[0];
+        let (major, info) = (head >> 5, head & 0x1f);
+        match major {
+            0 => Ok(Item::Uint(self.argument(info)?)),
+            1 => Ok(Item::NegInt(self.argument(info)?)),
+            2 => {
+                let length = self.length(info)?;
+                Ok(Item::Bytes(self.take(length)?.to_vec()))
+            }
+            3 => {
+                let length = self.length(info)?;
+                let text = self.take(length)?;
+                String::from_utf8(text.to_vec()).map(Item::Text).map_err(|_| start)
+            }
+            5 => {
+                let length = self.length(info)?;
+                let mut entries = Vec::with_capacity(length);
+                for _ in 0..length {
+                    let key = self.item(depth + 1)?;
+                    let value = self.item(depth + 1)?;
+                    entries.push((key, value));
+                }
+                Ok(Item::Map(entries))
+            }
+            7 => match info {
+                25 => Ok(Item::Float(f16_to_f64(self.uint(2)? as u16))),
+                26 => Ok(Item::Float(f32::from_bits(self.uint(4)? as u32) as f64)),
+                27 => Ok(Item::Float(f64::from_bits(self.uint(8)?))),
+                _ => Err(start),
+            },
+            // arrays and tags never appear in an argument map
+            _ => Err(start),
+        }
+    }
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (half >> 10 & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f64;
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => (1024.0 + mantissa) * 2f64.powi(exponent - 25),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{decode, encode, CborError, Item};
+
+    #[rstest]
+    #[case("00", Item::Uint(0))]
+    #[case("1903e8", Item::Uint(1000))]
+    #[case("1bffffffffffffffff", Item::Uint(u64::MAX))]
+    #[case("3863", Item::NegInt(99))]
+    #[case("f93c00", Item::Float(1.0))]
+    #[case("fa47c35000", Item::Float(100000.0))]
+    #[case("fb3ff199999999999a", Item::Float(1.1))]
+    #[case("4401020304", Item::Bytes(vec![1, 2, 3, 4]))]
+    #[case("6449455446", Item::Text("IETF".into()))]
+    #[case("a2614101614202", Item::Map(vec![(Item::Text("A".into()), Item::Uint(1)), (Item::Text("B".into()), Item::Uint(2))]))]
+    fn decodes(#[case] hex: &str, #[case] expected: Item) {
+        assert_eq!(decode(&base16::decode(hex).unwrap()), Ok(expected));
+    }
+
+    #[rstest]
+    #[case(Item::Uint(23), "17")]
+    #[case(Item::Uint(24), "1818")]
+    #[case(Item::Uint(65536), "1a00010000")]
+    #[case(Item::Uint(u64::MAX), "1bffffffffffffffff")]
+    #[case(Item::NegInt(0), "20")]
+    #[case(Item::Float(1.0), "fb3ff0000000000000")]
+    #[case(Item::Text("IETF".into()), "6449455446")]
+    #[case(Item::Map(vec![(Item::Text("A".into()), Item::Bytes(vec![1]))]), "a161414101")]
+    fn encodes(#[case] item: Item, #[case] hex: &str) {
+        let mut out = vec![];
+        encode(&item, &mut out);
+        assert_eq!(base16::encode_lower(&out), hex);
+        assert_eq!(decode(&out), Ok(item));
+    }
+
+    #[rstest]
+    #[case("", 0)]
+    #[case("19e8", 1)]
+    #[case("5f", 1)]
+    #[case("45010203", 1)]
+    #[case("8101", 0)]
+    #[case("c11a514b67b0", 0)]
+    #[case("62c328", 0)]
+    #[case("0000", 1)]
+    #[case("a1a1a1a1a1a10000", 5)]
+    fn rejects(#[case] hex: &str, #[case] offset: usize) {
+        assert_eq!(decode(&base16::decode(hex).unwrap()), Err(CborError { offset }));
+    }
+}
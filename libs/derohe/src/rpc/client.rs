@@ -0,0 +1,233 @@
+use std::{error, fmt, io};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::daemon_rpc::*;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error object of a JSON-RPC 2.0 response.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Transport(Box<ureq::Error>),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Rpc(RpcError),
+    /// The response had neither a result nor an error.
+    EmptyResponse,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "request failed: {}", e),
+            ClientError::Io(e) => write!(f, "unable to read response: {}", e),
+            ClientError::Json(e) => write!(f, "invalid json: {}", e),
+            ClientError::Rpc(e) => write!(f, "rpc error {}: {}", e.code, e.message),
+            ClientError::EmptyResponse => write!(f, "response has neither result nor error"),
+        }
+    }
+}
+
+impl error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ClientError::Transport(e) => Some(e.as_ref()),
+            ClientError::Io(e) => Some(e),
+            ClientError::Json(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> Self {
+        ClientError::Json(e)
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a, P: Serialize> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<&'a P>,
+}
+
+#[derive(Deserialize)]
+struct Response<R> {
+    #[serde(default = "Option::default")]
+    result: Option<R>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+/// Blocking JSON-RPC 2.0 client for the daemon RPC endpoint.
+#[derive(Debug)]
+pub struct Client {
+    url: String,
+    agent: ureq::Agent,
+    id: AtomicU64,
+}
+
+impl Client {
+    /// `address` is either `host:port` or a full http or https url, `/json_rpc` is appended when no path is given.
+    pub fn new(address: &str) -> Self {
+        let mut url = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        };
+        if url.split_once("://").is_none_or(|(_, rest)| !rest.contains('/')) {
+            url.push_str("/json_rpc");
+        }
+        Client {
+            url,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: Option<&P>) -> Result<R, ClientError> {
+        let request = Request {
+            jsonrpc: "2.0",
+            id: self.id.fetch_add(1, Ordering::Relaxed),
+            method,
+            params,
+        };
+        let body = serde_json::to_string(&request)?;
+        let response = match self.agent.post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body) {
+            Ok(response) => response,
+            // JSON-RPC servers may report errors with a non 200 status, the body still has the error object
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(ClientError::Transport(Box::new(e))),
+        };
+        parse_response(&response.into_string()?)
+    }
+
+    pub fn get_info(&self) -> Result<GetInfoResult, ClientError> {
+        self.call::<(), _>("DERO.GetInfo", None)
+    }
+
+    pub fn get_height(&self) -> Result<GetHeightResult, ClientError> {
+        self.call::<(), _>("DERO.GetHeight", None)
+    }
+
+    pub fn get_block_header_by_height(&self, height: u64) -> Result<GetBlockHeaderByHeightResult, ClientError> {
+        self.call("DERO.GetBlockHeaderByHeight", Some(&GetBlockHeaderByHeightParams { Height: height }))
+    }
+
+    pub fn get_block(&self, params: &GetBlockParams) -> Result<GetBlockResult, ClientError> {
+        self.call("DERO.GetBlock", Some(params))
+    }
+
+    pub fn get_block_template(&self, params: &GetBlockTemplatePararms) -> Result<GetBlockTemplateResult, ClientError> {
+        self.call("DERO.GetBlockTemplate", Some(params))
+    }
+
+    pub fn submit_block(&self, params: &SubmitBlockParams) -> Result<SubmitBlockResult, ClientError> {
+        self.call("DERO.SubmitBlock", Some(params))
+    }
+
+    pub fn get_encrypted_balance(&self, params: &GetEncryptedBalanceParams) -> Result<GetEncryptedBalanceResult, ClientError> {
+        self.call("DERO.GetEncryptedBalance", Some(params))
+    }
+}
+
+fn parse_response<R: DeserializeOwned>(body: &str) -> Result<R, ClientError> {
+    let response: Response<R> = serde_json::from_str(body)?;
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(ClientError::Rpc(error)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err(ClientError::EmptyResponse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use rstest::rstest;
+
+    use super::{Client, ClientError, parse_response};
+    use crate::rpc::daemon_rpc::GetHeightResult;
+
+    #[rstest]
+    #[case("127.0.0.1:10102", "http://127.0.0.1:10102/json_rpc")]
+    #[case("http://node:10102", "http://node:10102/json_rpc")]
+    #[case("https://node/rpc", "https://node/rpc")]
+    fn client_url(#[case] address: &str, #[case] expected: &str) {
+        assert_eq!(Client::new(address).url(), expected);
+    }
+
+    #[test]
+    fn rpc_error() {
+        let result = parse_response::<GetHeightResult>(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"method not found"}}"#);
+        match result {
+            Err(ClientError::Rpc(e)) => {
+                assert_eq!(e.code, -32601);
+                assert_eq!(e.message, "method not found");
+            }
+            _ => panic!("expected rpc error"),
+        }
+        assert!(matches!(parse_response::<GetHeightResult>(r#"{"jsonrpc":"2.0","id":1}"#), Err(ClientError::EmptyResponse)));
+    }
+
+    #[test]
+    fn get_height() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let response = r#"{"jsonrpc":"2.0","id":1,"result":{"height":1234,"stableheight":1226,"topoheight":1300,"status":"OK"}}"#;
+            write!(reader.get_mut(), "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", response.len(), response).unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        let result = Client::new(&address).get_height().unwrap();
+        assert_eq!(result.Height, 1234);
+        assert_eq!(result.TopoHeight, 1300);
+        let request = server.join().unwrap();
+        assert_eq!(request, r#"{"jsonrpc":"2.0","id":1,"method":"DERO.GetHeight"}"#);
+    }
+}
@@ -1,3 +1,6 @@
+// field names mirror the Go daemon structs
+#![allow(non_snake_case)]
+
 use super::helpers;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -10,7 +13,7 @@ pub struct GetBlockTemplatePararms {
     pub Miner: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct GetBlockTemplateResult {
     #[serde(rename = "jobid")]
     pub JobID: String,
@@ -36,4 +39,193 @@ pub struct GetBlockTemplateResult {
     pub LastError: String,
     #[serde(rename = "status")]
     pub Status: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetInfoResult {
+    #[serde(default, rename = "height")]
+    pub Height: i64,
+    #[serde(default, rename = "stableheight")]
+    pub StableHeight: i64,
+    #[serde(default, rename = "topoheight")]
+    pub TopoHeight: i64,
+    #[serde(default, rename = "treehash")]
+    pub Merkle_Balance_TreeHash: String,
+    #[serde(default, rename = "averageblocktime50")]
+    pub AverageBlockTime50: f32,
+    #[serde(default, rename = "target")]
+    pub Target: u64,
+    #[serde(default, rename = "difficulty")]
+    pub Difficulty: u64,
+    #[serde(default, rename = "top_block_hash")]
+    pub Top_block_hash: String,
+    #[serde(default, rename = "tx_count")]
+    pub Tx_count: u64,
+    #[serde(default, rename = "tx_pool_size")]
+    pub Tx_pool_size: u64,
+    #[serde(default, rename = "dynamic_fee_per_kb")]
+    pub Dynamic_fee_per_kb: u64,
+    #[serde(default, rename = "total_supply")]
+    pub Total_Supply: u64,
+    #[serde(default, rename = "median_block_size")]
+    pub Median_Block_Size: u64,
+    #[serde(default, rename = "white_peerlist_size")]
+    pub White_peerlist_size: u64,
+    #[serde(default, rename = "version")]
+    pub Version: String,
+    #[serde(default, rename = "connected_miners")]
+    pub Miners: i64,
+    #[serde(default, rename = "incoming_connections_count")]
+    pub Incoming_connections_count: u64,
+    #[serde(default, rename = "outgoing_connections_count")]
+    pub Outgoing_connections_count: u64,
+    #[serde(default, rename = "uptime")]
+    pub Uptime: u64,
+    #[serde(default, rename = "network")]
+    pub Network: String,
+    #[serde(default, rename = "testnet")]
+    pub Testnet: bool,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetHeightResult {
+    #[serde(rename = "height")]
+    pub Height: i64,
+    #[serde(rename = "stableheight")]
+    pub StableHeight: i64,
+    #[serde(rename = "topoheight")]
+    pub TopoHeight: i64,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeaderPrint {
+    #[serde(default, rename = "depth")]
+    pub Depth: i64,
+    #[serde(default, rename = "difficulty")]
+    pub Difficulty: String,
+    #[serde(default, rename = "hash")]
+    pub Hash: String,
+    #[serde(default, rename = "height")]
+    pub Height: i64,
+    #[serde(default, rename = "topoheight")]
+    pub TopoHeight: i64,
+    #[serde(default, rename = "major_version")]
+    pub Major_Version: u64,
+    #[serde(default, rename = "minor_version")]
+    pub Minor_Version: u64,
+    #[serde(default, rename = "nonce")]
+    pub Nonce: u64,
+    #[serde(default, rename = "orphan_status")]
+    pub Orphan_Status: bool,
+    #[serde(default, rename = "syncblock")]
+    pub SyncBlock: bool,
+    #[serde(default, rename = "sideblock")]
+    pub SideBlock: bool,
+    #[serde(default, rename = "txcount")]
+    pub TXCount: i64,
+    #[serde(default, rename = "miners", deserialize_with = "helpers::null_to_default")]
+    pub Miners: Vec<String>,
+    #[serde(default, rename = "reward")]
+    pub Reward: u64,
+    #[serde(default, rename = "tips", deserialize_with = "helpers::null_to_default")]
+    pub Tips: Vec<String>,
+    #[serde(default, rename = "timestamp")]
+    pub Timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockHeaderByHeightParams {
+    #[serde(rename = "height")]
+    pub Height: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockHeaderByHeightResult {
+    #[serde(rename = "block_header")]
+    pub Block_Header: BlockHeaderPrint,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
+
+/// Blocks are looked up by `Hash` when it is set, otherwise by `Height`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockParams {
+    #[serde(default, rename = "hash", skip_serializing_if = "String::is_empty")]
+    pub Hash: String,
+    #[serde(default, rename = "height", skip_serializing_if = "helpers::is_zero")]
+    pub Height: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockResult {
+    #[serde(rename = "block_header")]
+    pub Block_Header: BlockHeaderPrint,
+    #[serde(default, rename = "blob")]
+    pub Blob: String,
+    #[serde(default, rename = "json")]
+    pub Json: String,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmitBlockParams {
+    #[serde(rename = "jobid")]
+    pub JobID: String,
+    #[serde(rename = "mbl_blob")]
+    pub MiniBlockhashing_blob: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubmitBlockResult {
+    #[serde(default, rename = "blid")]
+    pub BLID: String,
+    #[serde(default, rename = "mini")]
+    pub MiniBlock: bool,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetEncryptedBalanceParams {
+    #[serde(rename = "address")]
+    pub Address: String,
+    #[serde(rename = "scid")]
+    pub SCID: String,
+    #[serde(default, rename = "treehash", skip_serializing_if = "String::is_empty")]
+    pub Merkle_Balance_TreeHash: String,
+    #[serde(default, rename = "topoheight", skip_serializing_if = "helpers::is_zero")]
+    pub TopoHeight: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetEncryptedBalanceResult {
+    #[serde(default, rename = "scid")]
+    pub SCID: String,
+    #[serde(default, rename = "data")]
+    pub Data: String,
+    #[serde(default, rename = "registration")]
+    pub Registration: i64,
+    #[serde(default, rename = "bits")]
+    pub Bits: i64,
+    #[serde(default, rename = "height")]
+    pub Height: i64,
+    #[serde(default, rename = "topoheight")]
+    pub Topoheight: i64,
+    #[serde(default, rename = "blockhash")]
+    pub BlockHash: String,
+    #[serde(default, rename = "treehash")]
+    pub Merkle_Balance_TreeHash: String,
+    #[serde(default, rename = "dheight")]
+    pub DHeight: i64,
+    #[serde(default, rename = "dtopoheight")]
+    pub DTopoheight: i64,
+    #[serde(default, rename = "dtreehash")]
+    pub DMerkle_Balance_TreeHash: String,
+    #[serde(default, rename = "status")]
+    pub Status: String,
+}
@@ -7,4 +7,93 @@ pub fn null_to_default<'de, D, T>(de: D) -> Result<T, D::Error>
 {
     let key = Option::<T>::deserialize(de)?;
     Ok(key.unwrap_or_default())
-}
\ No newline at end of file
+}
+
+pub fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Formats unix seconds as an RFC 3339 UTC timestamp, what Go's `time.Time` marshals to in JSON.
+pub fn format_rfc3339(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    // days to civil date, from Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+/// Parses an RFC 3339 timestamp into unix seconds, fractions of a second are dropped.
+pub fn parse_rfc3339(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || !matches!(bytes[10], b'T' | b't') || bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    let number = |text: &str, range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = text.get(range)?;
+        if digits.bytes().all(|b| b.is_ascii_digit()) { digits.parse().ok() } else { None }
+    };
+    let (year, month, day) = (number(text, 0..4)?, number(text, 5..7)?, number(text, 8..10)?);
+    let (hour, minute, second) = (number(text, 11..13)?, number(text, 14..16)?, number(text, 17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let mut rest = text.get(19..)?;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        rest = &fraction[digits..];
+    }
+    let offset = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let offset = number(rest, 1..3)? * 3600 + number(rest, 4..6)? * 60;
+            if *sign == b'-' { -offset } else { offset }
+        }
+        _ => return None
+    };
+    // civil date to days, the inverse of the above
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{format_rfc3339, parse_rfc3339};
+
+    #[rstest]
+    #[case(0, "1970-01-01T00:00:00Z")]
+    #[case(951782400, "2000-02-29T00:00:00Z")]
+    #[case(1650000000, "2022-04-15T05:20:00Z")]
+    #[case(-1, "1969-12-31T23:59:59Z")]
+    fn rfc3339(#[case] seconds: i64, #[case] text: &str) {
+        assert_eq!(format_rfc3339(seconds), text);
+        assert_eq!(parse_rfc3339(text), Some(seconds));
+    }
+
+    #[rstest]
+    #[case("2022-04-15")]
+    #[case("2022-04-15T05:20:00")]
+    #[case("2022-13-15T05:20:00Z")]
+    #[case("2022-04-15T05:20:00.Z")]
+    #[case("2022-04-15T05:20:00+0200")]
+    #[case("2022-04-15 05:20:00Z")]
+    fn rfc3339_rejects(#[case] text: &str) {
+        assert_eq!(parse_rfc3339(text), None);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod address;
+pub mod client;
 pub mod rpc;
 pub mod daemon_rpc;
 
+mod cbor;
 mod helpers;
@@ -1,26 +1,445 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::{error, fmt};
+
+use bn::G1;
+
+use crate::rpc::address::{Address, Network, ReversedG1};
+use crate::rpc::cbor::{self, CborError, Item};
+use crate::rpc::helpers;
+
+// argument names with a meaning to the Go wallet
+pub const RPC_DESTINATION_PORT: &str = "D";
+pub const RPC_SOURCE_PORT: &str = "S";
+pub const RPC_VALUE_TRANSFER: &str = "V";
+pub const RPC_COMMENT: &str = "C";
+pub const RPC_EXPIRY: &str = "E";
+pub const RPC_REPLYBACK_ADDRESS: &str = "R";
+pub const RPC_NEEDS_REPLYBACK_ADDRESS: &str = "N";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DataType {
+    #[serde(rename = "S")]
     String,
+    #[serde(rename = "I")]
     Int64,
+    #[serde(rename = "U")]
     Uint64,
+    #[serde(rename = "F")]
     Float64,
+    #[serde(rename = "H")]
     Hash,
+    #[serde(rename = "A")]
     Address,
+    #[serde(rename = "T")]
     Time,
 }
 
+impl DataType {
+    /// The suffix the Go wallet appends to argument names on the wire.
+    pub fn code(self) -> char {
+        match self {
+            DataType::String => 'S',
+            DataType::Int64 => 'I',
+            DataType::Uint64 => 'U',
+            DataType::Float64 => 'F',
+            DataType::Hash => 'H',
+            DataType::Address => 'A',
+            DataType::Time => 'T',
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        match code {
+            'S' => Some(DataType::String),
+            'I' => Some(DataType::Int64),
+            'U' => Some(DataType::Uint64),
+            'F' => Some(DataType::Float64),
+            'H' => Some(DataType::Hash),
+            'A' => Some(DataType::Address),
+            'T' => Some(DataType::Time),
+            _ => None
+        }
+    }
+}
+
+/// An argument value, one variant per `DataType`.
 #[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Int64(i64),
+    Uint64(u64),
+    Float64(f64),
+    Hash([u8; 32]),
+    Address(Address),
+    /// Seconds since the unix epoch.
+    Time(i64),
+}
+
+impl Value {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Value::String(_) => DataType::String,
+            Value::Int64(_) => DataType::Int64,
+            Value::Uint64(_) => DataType::Uint64,
+            Value::Float64(_) => DataType::Float64,
+            Value::Hash(_) => DataType::Hash,
+            Value::Address(_) => DataType::Address,
+            Value::Time(_) => DataType::Time,
+        }
+    }
+
+    /// Reads a CBOR item the way the Go wallet does for the given type, `None` if it doesn't fit.
+    /// Addresses only carry their key, they are put on `network`.
+    fn from_item(data_type: DataType, item: Item, network: Network) -> Option<Self> {
+        match (data_type, item) {
+            (DataType::String, Item::Text(text)) => Some(Value::String(text)),
+            (DataType::Int64, Item::Uint(n)) => i64::try_from(n).ok().map(Value::Int64),
+            (DataType::Int64, Item::NegInt(n)) => i64::try_from(n).ok().map(|n| Value::Int64(-1 - n)),
+            (DataType::Uint64, Item::Uint(n)) => Some(Value::Uint64(n)),
+            (DataType::Float64, Item::Float(f)) => Some(Value::Float64(f)),
+            (DataType::Hash, Item::Bytes(bytes)) => bytes.try_into().ok().map(Value::Hash),
+            (DataType::Address, Item::Bytes(bytes)) => {
+                G1::from_compressed_reversed_sign(&bytes).ok().map(|key| Value::Address(Address::new(key, network)))
+            }
+            (DataType::Time, Item::Uint(n)) => i64::try_from(n).ok().map(Value::Time),
+            (DataType::Time, Item::NegInt(n)) => i64::try_from(n).ok().map(|n| Value::Time(-1 - n)),
+            _ => None
+        }
+    }
+
+    fn to_item(&self) -> Item {
+        let signed = |n: i64| if n < 0 { Item::NegInt(!n as u64) } else { Item::Uint(n as u64) };
+        match self {
+            Value::String(text) => Item::Text(text.clone()),
+            Value::Int64(n) | Value::Time(n) => signed(*n),
+            Value::Uint64(n) => Item::Uint(*n),
+            Value::Float64(f) => Item::Float(*f),
+            Value::Hash(hash) => Item::Bytes(hash.to_vec()),
+            Value::Address(address) => Item::Bytes(address.public_key().to_compressed_reversed_sign().to_vec()),
+        }
+    }
+
+    /// The JSON form of Go's `Argument.Value`, hashes in hex, addresses in bech32 and times in RFC 3339.
+    fn from_json(data_type: DataType, json: serde_json::Value) -> Option<Self> {
+        match (data_type, json) {
+            (DataType::String, serde_json::Value::String(text)) => Some(Value::String(text)),
+            (DataType::Int64, json) => json.as_i64().map(Value::Int64),
+            (DataType::Uint64, json) => json.as_u64().map(Value::Uint64),
+            (DataType::Float64, json) => json.as_f64().map(Value::Float64),
+            (DataType::Hash, serde_json::Value::String(hex)) => base16::decode(&hex).ok()?.try_into().ok().map(Value::Hash),
+            (DataType::Address, serde_json::Value::String(text)) => Address::from_string(&text).ok().map(Value::Address),
+            (DataType::Time, serde_json::Value::String(text)) => helpers::parse_rfc3339(&text).map(Value::Time),
+            _ => None
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::String(text) => text.clone().into(),
+            Value::Int64(n) => (*n).into(),
+            Value::Uint64(n) => (*n).into(),
+            Value::Float64(f) => (*f).into(),
+            Value::Hash(hash) => base16::encode_lower(hash).into(),
+            Value::Address(address) => address.to_string().into(),
+            Value::Time(seconds) => helpers::format_rfc3339(*seconds).into(),
+        }
+    }
+}
+
+/// Values print as in JSON, without quotes around text.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_json() {
+            serde_json::Value::String(text) => f.write_str(&text),
+            json => write!(f, "{}", json),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "JsonArgument", into = "JsonArgument")]
 pub struct Argument {
     name: String,
-    data_type: DataType,
-    // Value
+    value: Value,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// How Go's `rpc.Argument` looks in JSON-RPC.
+#[derive(Serialize, Deserialize)]
+struct JsonArgument {
+    name: String,
+    datatype: DataType,
+    value: serde_json::Value,
+}
+
+impl TryFrom<JsonArgument> for Argument {
+    type Error = ArgumentsError;
+
+    fn try_from(json: JsonArgument) -> Result<Self, Self::Error> {
+        match Value::from_json(json.datatype, json.value) {
+            Some(value) => Ok(Argument { name: json.name, value }),
+            None => Err(ArgumentsError::InvalidValue(json.name, json.datatype))
+        }
+    }
+}
+
+impl From<Argument> for JsonArgument {
+    fn from(argument: Argument) -> Self {
+        JsonArgument { datatype: argument.data_type(), value: argument.value.to_json(), name: argument.name }
+    }
+}
+
+impl Argument {
+    pub fn new(name: impl Into<String>, value: Value) -> Self {
+        Argument { name: name.into(), value }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.value.data_type()
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Arguments(Vec<Argument>);
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentsError {
+    Cbor(CborError),
+    /// The payload is valid CBOR but not a map keyed by text.
+    NotAMap,
+    /// A key too short to hold a name and a type.
+    InvalidName(String),
+    UnknownDataType(String),
+    InvalidValue(String, DataType),
+    /// Two arguments share a name and a type, the second would overwrite the first on the wire.
+    Duplicate(String, DataType),
+}
+
+impl fmt::Display for ArgumentsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentsError::Cbor(e) => write!(f, "invalid cbor at byte {}", e.offset),
+            ArgumentsError::NotAMap => write!(f, "arguments are not a map"),
+            ArgumentsError::InvalidName(key) => write!(f, "invalid argument name: {:?}", key),
+            ArgumentsError::UnknownDataType(key) => write!(f, "unknown data type in argument {:?}", key),
+            ArgumentsError::InvalidValue(name, data_type) => write!(f, "argument {} is not a valid {:?}", name, data_type),
+            ArgumentsError::Duplicate(name, data_type) => write!(f, "argument {} of type {:?} appears twice", name, data_type),
+        }
+    }
+}
+
+impl error::Error for ArgumentsError {}
+
+impl From<CborError> for ArgumentsError {
+    fn from(e: CborError) -> Self {
+        ArgumentsError::Cbor(e)
+    }
+}
+
 impl Arguments {
     pub fn empty() -> Self {
         Arguments(vec![])
     }
-}
\ No newline at end of file
+
+    pub fn new(arguments: Vec<Argument>) -> Self {
+        Arguments(arguments)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&Argument> {
+        self.0.iter()
+    }
+
+    pub fn has(&self, name: &str, data_type: DataType) -> bool {
+        self.value(name, data_type).is_some()
+    }
+
+    pub fn value(&self, name: &str, data_type: DataType) -> Option<&Value> {
+        self.0.iter().find(|argument| argument.name == name && argument.data_type() == data_type).map(Argument::value)
+    }
+
+    /// Checks the arguments survive a round trip through the wire format: names can't be empty and the
+    /// name and type pairs, which become the map keys, must be unique.
+    pub fn validate(&self) -> Result<(), ArgumentsError> {
+        for (i, argument) in self.0.iter().enumerate() {
+            if argument.name.is_empty() {
+                return Err(ArgumentsError::InvalidName(argument.name.clone()));
+            }
+            if self.0[..i].iter().any(|other| other.name == argument.name && other.data_type() == argument.data_type()) {
+                return Err(ArgumentsError::Duplicate(argument.name.clone(), argument.data_type()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Follows Go's `Arguments.MarshalBinary`, keys sorted in CBOR canonical order (shorter first, then bytewise)
+    /// so the same arguments always give the same bytes. Not yet checked byte for byte against Go output.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut entries: Vec<(Item, Item)> = self.0.iter()
+            .map(|argument| (Item::Text(format!("{}{}", argument.name, argument.data_type().code())), argument.value.to_item()))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Item::Text(a), Item::Text(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+            _ => unreachable!("keys are text"),
+        });
+        let mut bytes = vec![];
+        cbor::encode(&Item::Map(entries), &mut bytes);
+        bytes
+    }
+
+    /// Decodes the CBOR map written by Go's `Arguments.MarshalBinary`, keys are the name followed by the type code.
+    /// A repeated key replaces the earlier one, as it does in the Go map. Address values go on `network`, the
+    /// one of the address carrying the arguments.
+    pub fn from_cbor(bytes: &[u8], network: Network) -> Result<Self, ArgumentsError> {
+        let entries = match cbor::decode(bytes)? {
+            Item::Map(entries) => entries,
+            _ => return Err(ArgumentsError::NotAMap)
+        };
+        let mut arguments: Vec<Argument> = Vec::with_capacity(entries.len());
+        for (key, item) in entries {
+            let key = match key {
+                Item::Text(key) => key,
+                _ => return Err(ArgumentsError::NotAMap)
+            };
+            let mut name = key.clone();
+            let data_type = match name.pop() {
+                Some(code) if !name.is_empty() => DataType::from_code(code).ok_or(ArgumentsError::UnknownDataType(key))?,
+                _ => return Err(ArgumentsError::InvalidName(key))
+            };
+            let value = Value::from_item(data_type, item, network).ok_or_else(|| ArgumentsError::InvalidValue(name.clone(), data_type))?;
+            arguments.retain(|argument| argument.name != name || argument.data_type() != data_type);
+            arguments.push(Argument { name, value });
+        }
+        Ok(Arguments(arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::rpc::cbor::CborError;
+    use crate::rpc::address::{Address, Network};
+    use crate::rpc::rpc::{Argument, Arguments, ArgumentsError, DataType, RPC_COMMENT, RPC_DESTINATION_PORT, Value};
+
+    fn decode(hex: &str) -> Result<Arguments, ArgumentsError> {
+        Arguments::from_cbor(&base16::decode(hex).unwrap(), Network::Mainnet)
+    }
+
+    #[test]
+    fn decodes_every_type() {
+        // {"CS": "hi", "DU": 1337, "EI": -2, "FF": 1.5, "HH": h'07…07', "TT": 1650000000}
+        let arguments = decode(concat!("a6", "624353", "626869", "624455", "190539", "624549", "21", "624646", "fb3ff8000000000000",
+                                       "624848", "58200707070707070707070707070707070707070707070707070707070707070707",
+                                       "625454", "1a62590080")).unwrap();
+        assert_eq!(arguments, Arguments::new(vec![
+            Argument::new("C", Value::String("hi".into())),
+            Argument::new("D", Value::Uint64(1337)),
+            Argument::new("E", Value::Int64(-2)),
+            Argument::new("F", Value::Float64(1.5)),
+            Argument::new("H", Value::Hash([7; 32])),
+            Argument::new("T", Value::Time(1650000000)),
+        ]));
+        assert_eq!(arguments.iter().map(Argument::data_type).collect::<Vec<_>>(),
+                   vec![DataType::String, DataType::Uint64, DataType::Int64, DataType::Float64, DataType::Hash, DataType::Time]);
+    }
+
+    #[rstest]
+    #[case("", ArgumentsError::Cbor(CborError { offset: 0 }))]
+    #[case("01", ArgumentsError::NotAMap)]
+    #[case("a10101", ArgumentsError::NotAMap)]
+    #[case("a1615501", ArgumentsError::InvalidName("U".into()))]
+    #[case("a162445801", ArgumentsError::UnknownDataType("DX".into()))]
+    #[case("a162445520", ArgumentsError::InvalidValue("D".into(), DataType::Uint64))]
+    #[case("a16248484101", ArgumentsError::InvalidValue("H".into(), DataType::Hash))]
+    fn rejects(#[case] hex: &str, #[case] expected: ArgumentsError) {
+        assert_eq!(decode(hex), Err(expected));
+    }
+
+    #[test]
+    fn encodes_like_go() {
+        // the key order is canonical whatever order the arguments were given in
+        let arguments = Arguments::new(vec![
+            Argument::new("T", Value::Time(1650000000)),
+            Argument::new("H", Value::Hash([7; 32])),
+            Argument::new("F", Value::Float64(1.5)),
+            Argument::new("E", Value::Int64(-2)),
+            Argument::new("D", Value::Uint64(1337)),
+            Argument::new("C", Value::String("hi".into())),
+        ]);
+        assert_eq!(base16::encode_lower(&arguments.to_cbor()), concat!(
+            "a6", "624353", "626869", "624455", "190539", "624549", "21", "624646", "fb3ff8000000000000",
+            "624848", "58200707070707070707070707070707070707070707070707070707070707070707",
+            "625454", "1a62590080"));
+        // longer keys sort after shorter ones
+        let arguments = Arguments::new(vec![Argument::new("AA", Value::Uint64(1)), Argument::new("B", Value::Uint64(2))]);
+        assert_eq!(base16::encode_lower(&arguments.to_cbor()), "a2624255026341415501");
+    }
+
+    #[test]
+    fn address_value_round_trip() {
+        let address = Address::from_string("dero1qykyta6ntpd27nl0yq4xtzaf4ls6p5e9pqu0k2x4x3pqq5xavjsdxqgny8270").unwrap();
+        let arguments = Arguments::new(vec![Argument::new("R", Value::Address(address))]);
+        assert_eq!(base16::encode_lower(&arguments.to_cbor()),
+                   "a162524158212c45f753585aaf4fef202a658ba9afe1a0d3250838fb28d534420050dd64a0d301");
+        assert_eq!(Arguments::from_cbor(&arguments.to_cbor(), Network::Mainnet), Ok(arguments));
+    }
+
+    #[test]
+    fn address_value_takes_network() {
+        let address = Address::from_string("dero1qykyta6ntpd27nl0yq4xtzaf4ls6p5e9pqu0k2x4x3pqq5xavjsdxqgny8270").unwrap().with_network(Network::Testnet);
+        let arguments = Arguments::new(vec![Argument::new("R", Value::Address(address))]);
+        assert_eq!(Arguments::from_cbor(&arguments.to_cbor(), Network::Testnet), Ok(arguments));
+    }
+
+    #[test]
+    fn lookup() {
+        let arguments = Arguments::new(vec![
+            Argument::new(RPC_DESTINATION_PORT, Value::Uint64(1337)),
+            Argument::new(RPC_COMMENT, Value::String("hi".into())),
+        ]);
+        assert!(arguments.has(RPC_DESTINATION_PORT, DataType::Uint64));
+        assert!(!arguments.has(RPC_DESTINATION_PORT, DataType::Int64));
+        assert_eq!(arguments.value(RPC_COMMENT, DataType::String), Some(&Value::String("hi".into())));
+        assert_eq!(arguments.value("X", DataType::String), None);
+    }
+
+    #[rstest]
+    #[case(vec![Argument::new("D", Value::Uint64(1)), Argument::new("D", Value::Int64(1))], Ok(()))]
+    #[case(vec![Argument::new("", Value::Uint64(1))], Err(ArgumentsError::InvalidName("".into())))]
+    #[case(vec![Argument::new("D", Value::Uint64(1)), Argument::new("D", Value::Uint64(2))], Err(ArgumentsError::Duplicate("D".into(), DataType::Uint64)))]
+    fn validate(#[case] arguments: Vec<Argument>, #[case] expected: Result<(), ArgumentsError>) {
+        assert_eq!(Arguments::new(arguments).validate(), expected);
+    }
+
+    #[test]
+    fn json() {
+        let json = r#"[{"name":"C","datatype":"S","value":"hi"},{"name":"D","datatype":"U","value":1337},{"name":"E","datatype":"I","value":-2},{"name":"F","datatype":"F","value":1.5},{"name":"H","datatype":"H","value":"0707070707070707070707070707070707070707070707070707070707070707"},{"name":"T","datatype":"T","value":"2022-04-15T05:20:00Z"}]"#;
+        let arguments: Arguments = serde_json::from_str(json).unwrap();
+        assert_eq!(arguments, decode(concat!("a6", "624353", "626869", "624455", "190539", "624549", "21", "624646", "fb3ff8000000000000",
+                                             "624848", "58200707070707070707070707070707070707070707070707070707070707070707",
+                                             "625454", "1a62590080")).unwrap());
+        assert_eq!(serde_json::to_string(&arguments).unwrap(), json);
+        // Go writes times in the wallet's zone
+        let local: Argument = serde_json::from_str(r#"{"name":"T","datatype":"T","value":"2022-04-15T07:20:00.5+02:00"}"#).unwrap();
+        assert_eq!(local.value(), &Value::Time(1650000000));
+        assert!(serde_json::from_str::<Argument>(r#"{"name":"D","datatype":"U","value":-1}"#).is_err());
+        let shown: Vec<String> = arguments.iter().map(|argument| argument.value().to_string()).collect();
+        assert_eq!(shown, ["hi", "1337", "-2", "1.5", "0707070707070707070707070707070707070707070707070707070707070707", "2022-04-15T05:20:00Z"]);
+    }
+
+    #[test]
+    fn repeated_name_keeps_last() {
+        // {"DU": 1, "DU": 10}
+        assert_eq!(decode("a2624455016244550a").unwrap(), Arguments::new(vec![Argument::new("D", Value::Uint64(10))]));
+    }
+}
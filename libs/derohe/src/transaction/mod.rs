@@ -0,0 +1,17 @@
+mod registration;
+
+pub use registration::{RegistrationTx, TransactionError, REGISTRATION_POW_ZEROS};
+
+/// The only transaction version the Go daemon knows.
+pub const TRANSACTION_VERSION: u64 = 1;
+
+/// `transaction.TransactionType` of the Go daemon, written as a uvarint after the version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionType {
+    Premine = 0,
+    Registration = 1,
+    Coinbase = 2,
+    Normal = 3,
+    Burn = 4,
+    SmartContract = 5,
+}
@@ -0,0 +1,246 @@
+//! The transaction registering a public key on chain, which has to happen before the key can receive anything.
+//!
+//! It carries a Schnorr signature over the key, made the way the Go wallet's `sign` does, and its hash has to
+//! start with `REGISTRATION_POW_ZEROS` zero bytes, which the wallet finds by signing with new nonces.
+
+use std::{error, fmt, thread};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bn::{AffineG1, Fq, Fr, G1, Group};
+use bn::arith::U256;
+use sha3::{Digest, Keccak256};
+
+use crate::rpc::address::ReversedG1;
+use crate::transaction::{TransactionType, TRANSACTION_VERSION};
+use crate::wallet::keys::SecretKey;
+
+/// Leading zero bytes the daemon wants in a registration's hash.
+pub const REGISTRATION_POW_ZEROS: usize = 3;
+
+/// Version, type, public key, `c` and `s`.
+const SERIALIZED_SIZE: usize = 2 + 33 + 32 + 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistrationTx {
+    public_key: [u8; 33],
+    c: [u8; 32],
+    s: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    InvalidLength(usize),
+    UnknownVersion(u8),
+    /// Not a registration.
+    UnexpectedType(u8),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::InvalidLength(length) => write!(f, "invalid registration length: {}", length),
+            TransactionError::UnknownVersion(version) => write!(f, "unknown transaction version: {}", version),
+            TransactionError::UnexpectedType(kind) => write!(f, "transaction type {} is not a registration", kind),
+        }
+    }
+}
+
+impl error::Error for TransactionError {}
+
+/// Signs with a key, keeping what every nonce reuses.
+struct Signer {
+    secret: Fr,
+    public_key: [u8; 33],
+    /// The public key as Go's `bn256.G1.String` prints it, which is what gets hashed.
+    public_string: String,
+}
+
+impl Signer {
+    fn new(secret: &SecretKey) -> Self {
+        let public_key = secret.public_key();
+        Signer {
+            secret: secret.scalar(),
+            public_key: public_key.to_compressed_reversed_sign(),
+            public_string: go_string(public_key).expect("the key isn't zero; qed"),
+        }
+    }
+
+    /// `point` must be the generator times `nonce`.
+    fn sign(&self, nonce: Fr, point: G1) -> RegistrationTx {
+        let c = challenge(&self.public_string, &go_string(point).expect("the nonce isn't zero; qed"));
+        let s = c * self.secret + nonce;
+        RegistrationTx { public_key: self.public_key, c: scalar_bytes(c), s: scalar_bytes(s) }
+    }
+}
+
+impl RegistrationTx {
+    /// Signs with the given nonce, which must be secret, random and never reused. The result usually lacks the
+    /// proof of work, see `build`.
+    pub fn sign(secret: &SecretKey, nonce: Fr) -> Self {
+        Signer::new(secret).sign(nonce, G1::one() * nonce)
+    }
+
+    /// Signs on `threads` threads until a signature gives the hash its proof of work, about 2²⁴ signatures.
+    pub fn build(secret: &SecretKey, threads: usize) -> Self {
+        RegistrationTx::search(secret, threads.max(1), REGISTRATION_POW_ZEROS)
+    }
+
+    fn search(secret: &SecretKey, threads: usize, zeros: usize) -> Self {
+        let signer = Signer::new(secret);
+        let done = AtomicBool::new(false);
+        let found = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    // a random start and then the following nonces, only the one signature that wins gets published
+                    let mut nonce = SecretKey::generate().scalar();
+                    let mut point = G1::one() * nonce;
+                    while !done.load(Ordering::Relaxed) {
+                        let tx = signer.sign(nonce, point);
+                        if tx.hash()[..zeros].iter().all(|&b| b == 0) {
+                            done.store(true, Ordering::Relaxed);
+                            *found.lock().unwrap() = Some(tx);
+                            return;
+                        }
+                        nonce = nonce + Fr::one();
+                        point = point + G1::one();
+                    }
+                });
+            }
+        });
+        found.into_inner().unwrap().expect("a thread found it before stopping; qed")
+    }
+
+    /// Reads what `to_bytes` writes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TransactionError> {
+        if bytes.len() != SERIALIZED_SIZE {
+            return Err(TransactionError::InvalidLength(bytes.len()));
+        }
+        if bytes[0] as u64 != TRANSACTION_VERSION {
+            return Err(TransactionError::UnknownVersion(bytes[0]));
+        }
+        if bytes[1] != TransactionType::Registration as u8 {
+            return Err(TransactionError::UnexpectedType(bytes[1]));
+        }
+        let mut tx = RegistrationTx { public_key: [0; 33], c: [0; 32], s: [0; 32] };
+        tx.public_key.copy_from_slice(&bytes[2..35]);
+        tx.c.copy_from_slice(&bytes[35..67]);
+        tx.s.copy_from_slice(&bytes[67..]);
+        Ok(tx)
+    }
+
+    /// Serializes like Go's `Transaction.Serialize`, for a registration only the header: the version and type as
+    /// uvarints, both fit a byte, then the public key, `c` and `s`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SERIALIZED_SIZE);
+        bytes.push(TRANSACTION_VERSION as u8);
+        bytes.push(TransactionType::Registration as u8);
+        bytes.extend_from_slice(&self.public_key);
+        bytes.extend_from_slice(&self.c);
+        bytes.extend_from_slice(&self.s);
+        bytes
+    }
+
+    pub fn public_key(&self) -> [u8; 33] {
+        self.public_key
+    }
+
+    /// The transaction id, Keccak-256 of the serialized transaction.
+    pub fn hash(&self) -> [u8; 32] {
+        Keccak256::digest(&self.to_bytes()).into()
+    }
+
+    pub fn has_pow(&self) -> bool {
+        self.hash()[..REGISTRATION_POW_ZEROS].iter().all(|&b| b == 0)
+    }
+
+    /// Checks the signature like Go's `IsRegistrationValid`: the nonce point is rebuilt as `s·G - c·U` and has to
+    /// hash back to `c`.
+    pub fn is_signature_valid(&self) -> bool {
+        self.verify().unwrap_or(false)
+    }
+
+    fn verify(&self) -> Option<bool> {
+        let public_key = G1::from_compressed_reversed_sign(&self.public_key).ok()?;
+        let c = Fr::new(U256::from_slice(&self.c).ok()?)?;
+        let s = Fr::new(U256::from_slice(&self.s).ok()?)?;
+        let point = G1::one() * s + public_key * (-c);
+        Some(challenge(&go_string(public_key)?, &go_string(point)?) == c)
+    }
+}
+
+/// Go's `crypto.ReducedHash` of the two points printed one after the other.
+fn challenge(public_key: &str, point: &str) -> Fr {
+    let mut wide = [0u8; 64];
+    wide[32..].copy_from_slice(&Keccak256::new().chain(public_key).chain(point).finalize());
+    Fr::interpret(&wide)
+}
+
+/// What Go's `bn256.G1.String` prints, `None` for the point at infinity.
+fn go_string(point: G1) -> Option<String> {
+    let affine = AffineG1::from_jacobian(point)?;
+    Some(format!("bn256.G1({}, {})", fq_hex(affine.x()), fq_hex(affine.y())))
+}
+
+fn fq_hex(fq: Fq) -> String {
+    let mut bytes = [0u8; 32];
+    fq.into_u256().to_big_endian(&mut bytes).expect("the buffer holds 32 bytes; qed");
+    base16::encode_lower(&bytes)
+}
+
+fn scalar_bytes(fr: Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    fr.into_u256().to_big_endian(&mut bytes).expect("the buffer holds 32 bytes; qed");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::rpc::address::ReversedG1;
+    use crate::wallet::keys::SecretKey;
+
+    use super::{go_string, RegistrationTx, TransactionError};
+
+    #[test]
+    fn prints_like_go() {
+        let key = SecretKey::from_hex("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        assert_eq!(go_string(key.public_key()).unwrap(), "bn256.G1(0000000000000000000000000000000000000000000000000000000000000001, \
+                                                          0000000000000000000000000000000000000000000000000000000000000002)");
+    }
+
+    #[test]
+    fn tampering_breaks_the_signature() {
+        let key = SecretKey::generate();
+        let tx = RegistrationTx::sign(&key, SecretKey::generate().scalar());
+        assert!(tx.is_signature_valid());
+        for byte in [2, 34, 35, 66, 67, 98] {
+            let mut bytes = tx.to_bytes();
+            bytes[byte] ^= 1;
+            assert!(!RegistrationTx::from_bytes(&bytes).unwrap().is_signature_valid(), "byte {}", byte);
+        }
+        let other = SecretKey::generate().public_key();
+        let stolen = RegistrationTx { public_key: other.to_compressed_reversed_sign(), ..tx };
+        assert!(!stolen.is_signature_valid());
+    }
+
+    #[rstest]
+    #[case("", TransactionError::InvalidLength(0))]
+    #[case(&"0101".repeat(50), TransactionError::InvalidLength(100))]
+    #[case(&format!("02{}", "01".repeat(98)), TransactionError::UnknownVersion(2))]
+    #[case(&format!("0102{}", "00".repeat(97)), TransactionError::UnexpectedType(2))]
+    fn rejects(#[case] hex: &str, #[case] error: TransactionError) {
+        assert_eq!(RegistrationTx::from_bytes(&base16::decode(hex).unwrap()), Err(error));
+    }
+
+    #[test]
+    fn search() {
+        let key = SecretKey::generate();
+        let tx = RegistrationTx::search(&key, 2, 1);
+        assert_eq!(tx.hash()[0], 0);
+        assert!(tx.is_signature_valid());
+        assert_eq!(tx.public_key(), key.public_key().to_compressed_reversed_sign());
+    }
+}
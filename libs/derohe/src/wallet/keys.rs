@@ -0,0 +1,194 @@
+//! Wallet secret keys: a scalar of the BN256 group order, whose public key is the generator times the scalar.
+
+use std::{error, fmt, fs, io};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use bn::{Fr, G1, Group};
+use bn::arith::U256;
+
+use crate::rpc::address::{Address, Network};
+
+/// A non zero secret scalar, `Debug` doesn't print it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SecretKey(Fr);
+
+#[derive(Debug)]
+pub enum KeyError {
+    InvalidHex(base16::DecodeError),
+    InvalidLength(usize),
+    /// The number isn't below the group order.
+    OutOfRange,
+    /// Zero has no usable public key.
+    Zero,
+    Io(io::Error),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::InvalidHex(e) => write!(f, "invalid secret key hex: {}", e),
+            KeyError::InvalidLength(length) => write!(f, "invalid secret key length: {} bytes, expected 32", length),
+            KeyError::OutOfRange => write!(f, "secret key is not below the group order"),
+            KeyError::Zero => write!(f, "secret key is zero"),
+            KeyError::Io(e) => write!(f, "unable to access the secret key file: {}", e),
+        }
+    }
+}
+
+impl error::Error for KeyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            KeyError::InvalidHex(e) => Some(e),
+            KeyError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for KeyError {
+    fn from(e: io::Error) -> Self {
+        KeyError::Io(e)
+    }
+}
+
+impl SecretKey {
+    /// A fresh key from the operating system's random source.
+    pub fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        loop {
+            let secret = Fr::random(&mut rng);
+            if !secret.is_zero() {
+                return SecretKey(secret);
+            }
+        }
+    }
+
+    pub fn from_scalar(secret: Fr) -> Result<Self, KeyError> {
+        if secret.is_zero() {
+            return Err(KeyError::Zero);
+        }
+        Ok(SecretKey(secret))
+    }
+
+    /// Reads the 32 byte big endian form, which must already be reduced.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KeyError> {
+        let value = U256::from_slice(bytes).map_err(|_| KeyError::InvalidLength(bytes.len()))?;
+        SecretKey::from_scalar(Fr::new(value).ok_or(KeyError::OutOfRange)?)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.0.into_u256().to_big_endian(&mut bytes).expect("the buffer holds 32 bytes; qed");
+        bytes
+    }
+
+    /// Reads 64 hex digits, surrounding whitespace is ignored.
+    pub fn from_hex(hex: &str) -> Result<Self, KeyError> {
+        SecretKey::from_bytes(&base16::decode(hex.trim()).map_err(KeyError::InvalidHex)?)
+    }
+
+    pub fn to_hex(&self) -> String {
+        base16::encode_lower(&self.to_bytes())
+    }
+
+    pub fn read_from(path: &Path) -> Result<Self, KeyError> {
+        SecretKey::from_hex(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the hex form to a new file only the owner can read, an existing file is never overwritten.
+    pub fn write_to(&self, path: &Path) -> Result<(), KeyError> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        writeln!(file, "{}", self.to_hex())?;
+        Ok(())
+    }
+
+    pub fn scalar(&self) -> Fr {
+        self.0
+    }
+
+    pub fn public_key(&self) -> G1 {
+        G1::one() * self.0
+    }
+
+    pub fn address(&self, network: Network) -> Address {
+        Address::new(self.public_key(), network)
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use rstest::rstest;
+
+    use crate::rpc::address::Network;
+
+    use super::{KeyError, SecretKey};
+
+    // addresses computed with an independent implementation of the curve and bech32
+    #[rstest]
+    #[case("0000000000000000000000000000000000000000000000000000000000000001",
+           "dero1qyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqzqqmnweqh",
+           "deto1qyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqzqqp2jp9l")]
+    #[case("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+           "dero1qy2vvc2uf7lvlf9zcgvh46q49yzvutqdnk4ty2r9pxfet8yatsezcqguwts8t",
+           "deto1qy2vvc2uf7lvlf9zcgvh46q49yzvutqdnk4ty2r9pxfet8yatsezcqgxhhgzr")]
+    // the group order minus one, the negated generator
+    #[case("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+           "dero1qyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqzqg982472",
+           "deto1qyqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqzqgl7kdmz")]
+    fn known_addresses(#[case] hex: &str, #[case] mainnet: &str, #[case] testnet: &str) {
+        let key = SecretKey::from_hex(hex).unwrap();
+        assert_eq!(key.to_hex(), hex);
+        assert_eq!(key.address(Network::Mainnet).to_string(), mainnet);
+        assert_eq!(key.address(Network::Testnet).to_string(), testnet);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("xyz")]
+    #[case("0001")]
+    #[case("0000000000000000000000000000000000000000000000000000000000000000")]
+    #[case("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001")]
+    #[case("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")]
+    fn rejects(#[case] hex: &str) {
+        assert!(SecretKey::from_hex(hex).is_err());
+    }
+
+    #[test]
+    fn generate() {
+        let key = SecretKey::generate();
+        assert_ne!(key, SecretKey::generate());
+        assert_eq!(SecretKey::from_hex(&key.to_hex()).unwrap(), key);
+        assert_eq!(format!("{:?}", key), "SecretKey(..)");
+    }
+
+    #[test]
+    fn file() {
+        let path = env::temp_dir().join(format!("derohe-key-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let key = SecretKey::generate();
+        key.write_to(&path).unwrap();
+        assert!(matches!(key.write_to(&path), Err(KeyError::Io(_))));
+        assert_eq!(SecretKey::read_from(&path).unwrap(), key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
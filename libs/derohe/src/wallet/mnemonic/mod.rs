@@ -0,0 +1,202 @@
+//! The 25 word recovery phrase of the Go wallet, Electrum style as in Monero.
+//!
+//! The secret's 32 little endian bytes are cut into eight 4 byte words, each written as three list words, and a
+//! 25th word repeats one of the 24 as a checksum.
+
+mod english;
+
+use std::{error, fmt};
+
+use crate::wallet::keys::{KeyError, SecretKey};
+
+/// Every list holds this many words, three of them encode 32 bits.
+const LIST_SIZE: u64 = 1626;
+const SEED_WORDS: usize = 24;
+
+/// A word list, new languages only need their list and prefix length added to `LANGUAGES`.
+/// Only English is bundled so far, `tests/fixtures/gen_wordlists.go` writes the other lists of the Go wallet as modules.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    /// How many leading characters tell the words apart, only those go into the checksum.
+    pub prefix_len: usize,
+    pub words: &'static [&'static str; LIST_SIZE as usize],
+}
+
+pub static ENGLISH: Language = Language { name: "English", prefix_len: 3, words: &english::WORDS };
+
+/// The languages a phrase is looked up in.
+pub static LANGUAGES: &[&Language] = &[&ENGLISH];
+
+#[derive(Debug)]
+pub enum MnemonicError {
+    WordCount(usize),
+    /// No language has all the words of the phrase, the first one missing from the closest language.
+    UnknownWord(String),
+    /// Three words don't fit in 32 bits.
+    InvalidWords(usize),
+    InvalidChecksum,
+    InvalidKey(KeyError),
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::WordCount(count) => write!(f, "seed has {} words, expected 25", count),
+            MnemonicError::UnknownWord(word) => write!(f, "unknown seed word: {}", word),
+            MnemonicError::InvalidWords(index) => write!(f, "seed words {} to {} are not a valid group", index + 1, index + 3),
+            MnemonicError::InvalidChecksum => write!(f, "seed checksum word doesn't match"),
+            MnemonicError::InvalidKey(e) => write!(f, "seed is not a valid key: {}", e),
+        }
+    }
+}
+
+impl error::Error for MnemonicError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MnemonicError::InvalidKey(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<KeyError> for MnemonicError {
+    fn from(e: KeyError) -> Self {
+        MnemonicError::InvalidKey(e)
+    }
+}
+
+impl Language {
+    fn index(&self, word: &str) -> Option<u64> {
+        self.words.iter().position(|&candidate| candidate == word).map(|index| index as u64)
+    }
+
+    fn checksum_index(&self, words: &[&str]) -> usize {
+        let prefixes: String = words.iter().flat_map(|word| word.chars().take(self.prefix_len)).collect();
+        crc32(prefixes.as_bytes()) as usize % words.len()
+    }
+}
+
+/// The phrase for `secret` in `language`, words separated by single spaces.
+pub fn to_words(secret: &SecretKey, language: &Language) -> String {
+    let mut bytes = secret.to_bytes();
+    bytes.reverse();
+    let mut words: Vec<&str> = Vec::with_capacity(SEED_WORDS + 1);
+    for chunk in bytes.chunks(4) {
+        let value = u32::from_le_bytes(chunk.try_into().expect("chunks of 4 bytes; qed")) as u64;
+        let w1 = value % LIST_SIZE;
+        let w2 = (value / LIST_SIZE + w1) % LIST_SIZE;
+        let w3 = (value / LIST_SIZE / LIST_SIZE + w2) % LIST_SIZE;
+        words.extend([w1, w2, w3].iter().map(|&index| language.words[index as usize]));
+    }
+    words.push(words[language.checksum_index(&words)]);
+    words.join(" ")
+}
+
+/// Reads a 25 word phrase in any bundled language, the language is told by the words.
+pub fn from_words(phrase: &str) -> Result<(SecretKey, &'static Language), MnemonicError> {
+    let phrase = phrase.to_lowercase();
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if words.len() != SEED_WORDS + 1 {
+        return Err(MnemonicError::WordCount(words.len()));
+    }
+    let (language, indices) = find_language(&words)?;
+
+    let mut bytes = [0u8; 32];
+    for (i, group) in indices[..SEED_WORDS].chunks(3).enumerate() {
+        let (w1, w2, w3) = (group[0], group[1], group[2]);
+        let value = w1 + LIST_SIZE * ((LIST_SIZE - w1 + w2) % LIST_SIZE) + LIST_SIZE * LIST_SIZE * ((LIST_SIZE - w2 + w3) % LIST_SIZE);
+        let value = u32::try_from(value).map_err(|_| MnemonicError::InvalidWords(i * 3))?;
+        bytes[i * 4..i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+    }
+    if words[SEED_WORDS] != words[language.checksum_index(&words[..SEED_WORDS])] {
+        return Err(MnemonicError::InvalidChecksum);
+    }
+    bytes.reverse();
+    Ok((SecretKey::from_bytes(&bytes)?, language))
+}
+
+fn find_language(words: &[&str]) -> Result<(&'static Language, Vec<u64>), MnemonicError> {
+    let mut closest: Option<(usize, &str)> = None;
+    for &language in LANGUAGES {
+        let indices: Vec<Option<u64>> = words.iter().map(|word| language.index(word)).collect();
+        let known = indices.iter().filter(|index| index.is_some()).count();
+        match indices.iter().position(Option::is_none) {
+            None => return Ok((language, indices.into_iter().flatten().collect())),
+            Some(missing) if closest.is_none_or(|(most, _)| known > most) => closest = Some((known, words[missing])),
+            Some(_) => (),
+        }
+    }
+    let (_, word) = closest.expect("at least one language; qed");
+    Err(MnemonicError::UnknownWord(word.to_string()))
+}
+
+/// CRC-32 as in zlib and Go's `hash/crc32.ChecksumIEEE`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::wallet::keys::SecretKey;
+
+    use super::{crc32, from_words, to_words, MnemonicError, ENGLISH, LANGUAGES, LIST_SIZE};
+
+    #[test]
+    fn lenient_spacing_and_case() {
+        let phrase = "  Muppet imbalance amnesty hatchet vessel obnoxious fuming casket tether somewhere dinner apart lunar\n\
+                      vapidly scenic tail second hurried imagine logic rash until july kiosk IMAGINE ";
+        assert!(from_words(phrase).is_ok());
+    }
+
+    #[rstest]
+    #[case("abbey abbey", "seed has 2 words, expected 25")]
+    #[case("muppet imbalance amnesty hatchet vessel obnoxious fuming casket tether somewhere dinner apart lunar vapidly scenic tail second hurried imagine logic rash until july kiosk tail",
+           "seed checksum word doesn't match")]
+    #[case("muppet imbalance amnesty hatchet vessel obnoxious fuming casket tether somewhere dinner apart lunar vapidly scenic tail second hurried imagine logic rash until july bitcoin imagine",
+           "unknown seed word: bitcoin")]
+    // abbey abbey zoom is above 2³²
+    #[case("abbey abbey zoom abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey",
+           "seed words 1 to 3 are not a valid group")]
+    // all zeros
+    #[case("abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey abbey",
+           "seed is not a valid key: secret key is zero")]
+    fn rejects(#[case] phrase: &str, #[case] error: &str) {
+        assert_eq!(from_words(phrase).unwrap_err().to_string(), error);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &language in LANGUAGES {
+            for _ in 0..20 {
+                let secret = SecretKey::generate();
+                let (decoded, found) = from_words(&to_words(&secret, language)).unwrap();
+                assert_eq!(decoded, secret);
+                assert_eq!(found.name, language.name);
+            }
+        }
+    }
+
+    #[test]
+    fn word_lists() {
+        for &language in LANGUAGES {
+            // the prefixes tell the words apart
+            let mut prefixes: Vec<String> = language.words.iter().map(|word| word.chars().take(language.prefix_len).collect()).collect();
+            prefixes.sort();
+            prefixes.dedup();
+            assert_eq!(prefixes.len() as u64, LIST_SIZE, "{}", language.name);
+        }
+        assert!(LANGUAGES.iter().any(|language| language.name == ENGLISH.name));
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert!(matches!(from_words(""), Err(MnemonicError::WordCount(0))));
+    }
+}
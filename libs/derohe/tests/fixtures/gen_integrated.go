@@ -0,0 +1,74 @@
+//go:build ignore
+
+// Prints integrated addresses built by the Go wallet, with their plain address and arguments in JSON.
+//
+//	go run gen_integrated.go > integrated.txt
+package main
+
+import (
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"math/big"
+
+	"github.com/deroproject/derohe/cryptography/bn256"
+	"github.com/deroproject/derohe/cryptography/crypto"
+	"github.com/deroproject/derohe/rpc"
+)
+
+func address(secret string, mainnet bool) rpc.Address {
+	scalar, _ := new(big.Int).SetString(secret, 16)
+	address := rpc.NewAddressFromKeys(new(bn256.G1).ScalarBaseMult(scalar))
+	address.Mainnet = mainnet
+	return *address
+}
+
+func main() {
+	var hash crypto.Hash
+	sevens, _ := hex.DecodeString("0707070707070707070707070707070707070707070707070707070707070707")
+	copy(hash[:], sevens)
+
+	cases := []struct {
+		secret    string
+		mainnet   bool
+		arguments rpc.Arguments
+	}{
+		{"0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", true, rpc.Arguments{
+			{Name: rpc.RPC_COMMENT, DataType: rpc.DataString, Value: "payout for rig 7"},
+			{Name: rpc.RPC_DESTINATION_PORT, DataType: rpc.DataUint64, Value: uint64(1337)},
+			{Name: rpc.RPC_VALUE_TRANSFER, DataType: rpc.DataUint64, Value: uint64(250000)},
+		}},
+		{"01", false, rpc.Arguments{
+			{Name: rpc.RPC_COMMENT, DataType: rpc.DataString, Value: "hello"},
+			{Name: rpc.RPC_DESTINATION_PORT, DataType: rpc.DataUint64, Value: uint64(42)},
+			{Name: rpc.RPC_VALUE_TRANSFER, DataType: rpc.DataUint64, Value: uint64(1)},
+		}},
+		{"07", true, rpc.Arguments{
+			{Name: rpc.RPC_DESTINATION_PORT, DataType: rpc.DataUint64, Value: uint64(18446744073709551615)},
+		}},
+		// an address argument on testnet
+		{"07", false, rpc.Arguments{
+			{Name: rpc.RPC_REPLYBACK_ADDRESS, DataType: rpc.DataAddress, Value: address("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", false)},
+			{Name: rpc.RPC_NEEDS_REPLYBACK_ADDRESS, DataType: rpc.DataUint64, Value: uint64(1)},
+		}},
+		{"02c418f93312c048a3681b1fd47e3169daf3b71db4915d5da9f7f568a3652bdd", true, rpc.Arguments{
+			{Name: rpc.RPC_COMMENT, DataType: rpc.DataString, Value: "ünïcode ✓"},
+			{Name: rpc.RPC_DESTINATION_PORT, DataType: rpc.DataUint64, Value: uint64(0)},
+			{Name: "X", DataType: rpc.DataInt64, Value: int64(-5)},
+			{Name: "H", DataType: rpc.DataHash, Value: hash},
+		}},
+	}
+
+	fmt.Println("# Integrated addresses, one `integrated plain arguments` line each, the arguments as the Go wallet writes them in JSON.")
+	fmt.Println("# Regenerate with `go run gen_integrated.go > integrated.txt` against github.com/deroproject/derohe.")
+	for _, c := range cases {
+		plain := address(c.secret, c.mainnet)
+		integrated := plain.Clone()
+		integrated.Arguments = c.arguments
+		arguments, err := json.Marshal(c.arguments)
+		if err != nil {
+			panic(err)
+		}
+		fmt.Printf("%s %s %s\n", integrated.String(), plain.String(), arguments)
+	}
+}
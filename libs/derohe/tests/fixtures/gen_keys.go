@@ -0,0 +1,47 @@
+//go:build ignore
+
+// Prints secret keys with their compressed public keys and addresses the way the Go wallet derives them.
+//
+//	go run gen_keys.go > keys.txt
+package main
+
+import (
+	"encoding/binary"
+	"fmt"
+	"math/big"
+
+	"github.com/deroproject/derohe/cryptography/bn256"
+	"github.com/deroproject/derohe/rpc"
+	"golang.org/x/crypto/sha3"
+)
+
+const count = 20
+
+func main() {
+	secrets := []*big.Int{big.NewInt(1), big.NewInt(7)}
+	for _, encoded := range []string{
+		"0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+		// the group order minus one, the negated generator
+		"30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+		"02c418f93312c048a3681b1fd47e3169daf3b71db4915d5da9f7f568a3652bdd",
+	} {
+		secret, _ := new(big.Int).SetString(encoded, 16)
+		secrets = append(secrets, secret)
+	}
+	for i := uint32(0); len(secrets) < count; i++ {
+		seed := append([]byte("keys-kat"), 0, 0, 0, 0)
+		binary.LittleEndian.PutUint32(seed[8:], i)
+		hash := sha3.Sum256(seed)
+		secrets = append(secrets, new(big.Int).Mod(new(big.Int).SetBytes(hash[:]), bn256.Order))
+	}
+
+	fmt.Println("# Secret keys with their compressed public keys and addresses, one `secret key mainnet testnet` line each in hex.")
+	fmt.Println("# Regenerate with `go run gen_keys.go > keys.txt` against github.com/deroproject/derohe.")
+	for _, secret := range secrets {
+		public := new(bn256.G1).ScalarBaseMult(secret)
+		mainnet := rpc.NewAddressFromKeys(public)
+		testnet := rpc.NewAddressFromKeys(public)
+		testnet.Mainnet = false
+		fmt.Printf("%064x %x %s %s\n", secret, mainnet.Compressed(), mainnet.String(), testnet.String())
+	}
+}
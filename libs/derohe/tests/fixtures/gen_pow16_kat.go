@@ -0,0 +1,58 @@
+//go:build ignore
+
+// Prints the pow16 known answer corpus using the reference AstroBWT implementation.
+//
+//	go run gen_pow16_kat.go > pow16_kat.txt
+package main
+
+import (
+	"encoding/binary"
+	"encoding/hex"
+	"fmt"
+
+	"github.com/deroproject/derohe/astrobwt"
+	"golang.org/x/crypto/sha3"
+)
+
+const count = 3000
+
+func main() {
+	inputs := [][]byte{{}}
+	for _, length := range []int{1, 32, 47, 48, 49, 64, 100} {
+		zeros := make([]byte, length)
+		ones := make([]byte, length)
+		for i := range ones {
+			ones[i] = 0xff
+		}
+		inputs = append(inputs, zeros, ones)
+	}
+
+	template, _ := hex.DecodeString("415a9e0000008386b3fe9a18000000004c2d828f0583a86db9639d36548f817100000000deb3465e1d316f0000000201")
+	for _, nonce := range []uint32{0, 1, 2, 0xff, 0x100, 0xffff, 0x10000, 0x7fffffff, 0x80000000, 0xfffffffe, 0xffffffff} {
+		for _, id := range []byte{0, 1, 0x7f, 0xff} {
+			work := append([]byte{}, template...)
+			binary.BigEndian.PutUint32(work[43:47], nonce)
+			work[47] = id
+			inputs = append(inputs, work)
+		}
+	}
+
+	for i := uint32(0); len(inputs) < count; i++ {
+		seed := append([]byte("pow16-kat"), 0, 0, 0, 0)
+		binary.LittleEndian.PutUint32(seed[9:], i)
+		a := sha3.Sum256(seed)
+		b := sha3.Sum256(a[:])
+		inputs = append(inputs, append(a[:], b[:16]...))
+	}
+
+	fmt.Println("# pow16 known answers, one `input hash` pair per line in hex, the empty input is written as `-`.")
+	fmt.Println("# Regenerate with `go run gen_pow16_kat.go > pow16_kat.txt` against github.com/deroproject/derohe.")
+	for _, input := range inputs {
+		hash := astrobwt.POW16(input)
+		encoded := hex.EncodeToString(input)
+		if len(input) == 0 {
+			encoded = "-"
+		}
+		fmt.Printf("%s %x\n", encoded, hash)
+	}
+}
@@ -0,0 +1,37 @@
+//go:build ignore
+
+// Prints registration transactions of the Go wallet with their proof of work, as its register command finds them.
+// Each takes about 2²⁴ signatures.
+//
+//	go run gen_registration.go > registration.txt
+package main
+
+import (
+	"fmt"
+	"math/big"
+
+	"github.com/deroproject/derohe/walletapi"
+)
+
+func main() {
+	fmt.Println("# Registration transactions of the Go wallet, one `secret transaction hash` line each in hex.")
+	fmt.Println("# Regenerate with `go run gen_registration.go > registration.txt` against github.com/deroproject/derohe.")
+	for _, encoded := range []string{
+		"07",
+		"02c418f93312c048a3681b1fd47e3169daf3b71db4915d5da9f7f568a3652bdd",
+	} {
+		secret, _ := new(big.Int).SetString(encoded, 16)
+		wallet, err := walletapi.Create_Encrypted_Wallet_Memory("", secret)
+		if err != nil {
+			panic(err)
+		}
+		for {
+			tx := wallet.GetRegistrationTX()
+			hash := tx.GetHash()
+			if hash[0] == 0 && hash[1] == 0 && hash[2] == 0 {
+				fmt.Printf("%064x %x %x\n", secret, tx.Serialize(), hash[:])
+				break
+			}
+		}
+	}
+}
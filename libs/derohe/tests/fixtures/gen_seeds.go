@@ -0,0 +1,40 @@
+//go:build ignore
+
+// Prints the recovery phrase of a few secret keys in every language of the Go wallet, with their address.
+//
+//	go run gen_seeds.go > seeds.txt
+package main
+
+import (
+	"fmt"
+	"math/big"
+
+	"github.com/deroproject/derohe/cryptography/bn256"
+	"github.com/deroproject/derohe/rpc"
+	"github.com/deroproject/derohe/walletapi/mnemonics"
+)
+
+func main() {
+	var secrets []*big.Int
+	for _, encoded := range []string{
+		"01",
+		"07",
+		"0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+		// the group order minus one
+		"30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+		"02c418f93312c048a3681b1fd47e3169daf3b71db4915d5da9f7f568a3652bdd",
+	} {
+		secret, _ := new(big.Int).SetString(encoded, 16)
+		secrets = append(secrets, secret)
+	}
+
+	fmt.Println("# Seeds of the Go wallet, one tab separated `language secret address phrase` line each.")
+	fmt.Println("# Regenerate with `go run gen_seeds.go > seeds.txt` against github.com/deroproject/derohe.")
+	for _, language := range mnemonics.Languages {
+		for _, secret := range secrets {
+			address := rpc.NewAddressFromKeys(new(bn256.G1).ScalarBaseMult(secret))
+			phrase := mnemonics.Key_To_Words(secret, language.Name)
+			fmt.Printf("%s\t%064x\t%s\t%s\n", language.Name_English, secret, address.String(), phrase)
+		}
+	}
+}
@@ -0,0 +1,40 @@
+//go:build ignore
+
+// Writes every word list of the Go wallet as a module of `src/wallet/mnemonic`, then prints the `Language` of each.
+//
+//	cd ../../src/wallet/mnemonic && go run ../../../tests/fixtures/gen_wordlists.go
+package main
+
+import (
+	"fmt"
+	"os"
+	"strings"
+
+	"github.com/deroproject/derohe/walletapi/mnemonics"
+)
+
+func main() {
+	for _, language := range mnemonics.Languages {
+		module := strings.NewReplacer(" ", "_", "(", "", ")", "").Replace(strings.ToLower(language.Name_English))
+		var source strings.Builder
+		fmt.Fprintf(&source, "//! The %s list of the Electrum style mnemonic, the same %d words as the Go wallet.\n\n", language.Name_English, len(language.Words))
+		fmt.Fprintf(&source, "pub static WORDS: [&str; %d] = [\n", len(language.Words))
+		for i := 0; i < len(language.Words); i += 10 {
+			end := i + 10
+			if end > len(language.Words) {
+				end = len(language.Words)
+			}
+			quoted := make([]string, 0, 10)
+			for _, word := range language.Words[i:end] {
+				quoted = append(quoted, fmt.Sprintf("%q", word))
+			}
+			fmt.Fprintf(&source, "    %s,\n", strings.Join(quoted, ", "))
+		}
+		source.WriteString("];\n")
+		if err := os.WriteFile(module+".rs", []byte(source.String()), 0644); err != nil {
+			panic(err)
+		}
+		fmt.Printf("pub static %s: Language = Language { name: %q, prefix_len: %d, words: &%s::WORDS };\n",
+			strings.ToUpper(module), language.Name_English, language.Unique_Prefix_Length, module)
+	}
+}
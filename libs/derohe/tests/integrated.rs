@@ -0,0 +1,55 @@
+use derohe::rpc::address::{Address, Kind};
+use derohe::rpc::rpc::{Arguments, Value};
+
+// not yet Go output, see the fixture header
+const FIXTURE: &str = include_str!("fixtures/integrated.txt");
+
+fn vectors() -> Vec<(&'static str, &'static str, serde_json::Value)> {
+    FIXTURE.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            // the arguments go last, strings in them can hold spaces
+            let mut parts = line.splitn(3, ' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(integrated), Some(plain), Some(arguments)) => (integrated, plain, serde_json::from_str(arguments).unwrap()),
+                _ => panic!("line should be `integrated plain arguments`: {}", line),
+            }
+        })
+        .collect()
+}
+
+/// Go doesn't keep the arguments in order, compare them sorted by name and type.
+fn sorted(mut json: serde_json::Value) -> serde_json::Value {
+    json.as_array_mut().unwrap().sort_by_key(|argument| format!("{}{}", argument["name"], argument["datatype"]));
+    json
+}
+
+#[test]
+fn fixture_size() {
+    assert_eq!(vectors().len(), 5);
+}
+
+#[test]
+fn decodes_known_addresses() {
+    for (integrated, plain, arguments) in vectors() {
+        let address = Address::from_string(integrated).unwrap();
+        let plain = Address::from_string(plain).unwrap();
+        assert_eq!(address.kind(), Kind::Integrated);
+        assert_eq!(address.network(), plain.network());
+        assert_eq!(address.public_key(), plain.public_key());
+        assert_eq!(sorted(serde_json::to_value(address.arguments()).unwrap()), sorted(arguments), "address {}", integrated);
+        for argument in address.arguments().iter() {
+            if let Value::Address(value) = argument.value() {
+                assert_eq!(value.network(), address.network(), "argument {} of {}", argument.name(), integrated);
+            }
+        }
+    }
+}
+
+#[test]
+fn encodes_known_addresses() {
+    for (integrated, plain, arguments) in vectors() {
+        let arguments: Arguments = serde_json::from_value(arguments).unwrap();
+        assert_eq!(Address::from_string(plain).unwrap().with_arguments(arguments).to_string(), integrated);
+    }
+}
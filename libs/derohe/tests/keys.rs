@@ -0,0 +1,49 @@
+use derohe::rpc::address::{Address, Network, ReversedG1};
+use derohe::wallet::keys::SecretKey;
+
+// not yet Go output, see the fixture header
+const FIXTURE: &str = include_str!("fixtures/keys.txt");
+
+struct Vector<'a> {
+    secret: &'a str,
+    key: &'a str,
+    mainnet: &'a str,
+    testnet: &'a str,
+}
+
+fn vectors() -> Vec<Vector<'static>> {
+    FIXTURE.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split(' ').collect::<Vec<_>>()[..] {
+            [secret, key, mainnet, testnet] => Vector { secret, key, mainnet, testnet },
+            _ => panic!("line should be `secret key mainnet testnet`: {}", line),
+        })
+        .collect()
+}
+
+#[test]
+fn fixture_size() {
+    assert_eq!(vectors().len(), 20);
+}
+
+#[test]
+fn derives_known_keys() {
+    for vector in vectors() {
+        let secret = SecretKey::from_hex(vector.secret).unwrap();
+        let key = secret.address(Network::Mainnet).public_key();
+        assert_eq!(base16::encode_lower(&key.to_compressed_reversed_sign()), vector.key, "secret {}", vector.secret);
+        assert_eq!(secret.address(Network::Mainnet).to_string(), vector.mainnet);
+        assert_eq!(secret.address(Network::Testnet).to_string(), vector.testnet);
+    }
+}
+
+#[test]
+fn parses_known_addresses() {
+    for vector in vectors() {
+        for (encoded, network) in [(vector.mainnet, Network::Mainnet), (vector.testnet, Network::Testnet)] {
+            let address = Address::from_string(encoded).unwrap();
+            assert_eq!(base16::encode_lower(&address.public_key().to_compressed_reversed_sign()), vector.key, "address {}", encoded);
+            assert_eq!(address.network(), network);
+        }
+    }
+}
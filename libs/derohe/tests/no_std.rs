@@ -0,0 +1,18 @@
+use std::process::Command;
+
+/// A target without std, any use of std by the crate or a dependency fails to build there.
+const NO_STD_TARGET: &str = "thumbv7em-none-eabi";
+
+/// Checks the library without the `std` feature for `NO_STD_TARGET`, `rustup target add thumbv7em-none-eabi` first.
+#[test]
+fn builds_without_std() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--lib", "--no-default-features", "--target", NO_STD_TARGET])
+        .arg("--manifest-path").arg(format!("{}/Cargo.toml", manifest_dir))
+        // a separate target dir so this doesn't wait on the lock held by the running test build
+        .arg("--target-dir").arg(format!("{}/target/no_std", manifest_dir))
+        .output()
+        .expect("unable to run cargo");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
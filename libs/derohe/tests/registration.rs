@@ -0,0 +1,34 @@
+use derohe::rpc::address::ReversedG1;
+use derohe::transaction::{RegistrationTx, REGISTRATION_POW_ZEROS};
+use derohe::wallet::keys::SecretKey;
+
+// not yet Go output, see the fixture header
+const FIXTURE: &str = include_str!("fixtures/registration.txt");
+
+fn vectors() -> Vec<(&'static str, Vec<u8>, &'static str)> {
+    FIXTURE.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split(' ').collect::<Vec<_>>()[..] {
+            [secret, tx, hash] => (secret, base16::decode(tx).unwrap(), hash),
+            _ => panic!("line should be `secret transaction hash`: {}", line),
+        })
+        .collect()
+}
+
+#[test]
+fn fixture_size() {
+    assert_eq!(vectors().len(), 2);
+}
+
+#[test]
+fn verifies_known_registrations() {
+    for (secret, bytes, hash) in vectors() {
+        let tx = RegistrationTx::from_bytes(&bytes).unwrap();
+        let key = SecretKey::from_hex(secret).unwrap();
+        assert_eq!(tx.public_key(), key.public_key().to_compressed_reversed_sign(), "secret {}", secret);
+        assert!(tx.is_signature_valid(), "secret {}", secret);
+        assert_eq!(base16::encode_lower(&tx.hash()), hash);
+        assert_eq!(tx.has_pow(), hash.starts_with(&"00".repeat(REGISTRATION_POW_ZEROS)));
+        assert_eq!(tx.to_bytes(), bytes);
+    }
+}
@@ -0,0 +1,45 @@
+use derohe::rpc::address::Network;
+use derohe::wallet::keys::SecretKey;
+use derohe::wallet::mnemonic::{from_words, to_words, LANGUAGES};
+
+// not yet Go output and English only, see the fixture header
+const FIXTURE: &str = include_str!("fixtures/seeds.txt");
+
+struct Vector<'a> {
+    language: &'a str,
+    secret: &'a str,
+    address: &'a str,
+    phrase: &'a str,
+}
+
+fn vectors() -> Vec<Vector<'static>> {
+    FIXTURE.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split('\t').collect::<Vec<_>>()[..] {
+            [language, secret, address, phrase] => Vector { language, secret, address, phrase },
+            _ => panic!("line should be `language secret address phrase`: {}", line),
+        })
+        .collect()
+}
+
+#[test]
+fn every_language_is_bundled() {
+    for vector in vectors() {
+        assert!(LANGUAGES.iter().any(|language| language.name == vector.language), "no word list for {}", vector.language);
+    }
+}
+
+#[test]
+fn known_seeds() {
+    let vectors = vectors();
+    assert!(!vectors.is_empty());
+    for vector in vectors {
+        let Some(&language) = LANGUAGES.iter().find(|language| language.name == vector.language) else { continue };
+        let secret = SecretKey::from_hex(vector.secret).unwrap();
+        assert_eq!(to_words(&secret, language), vector.phrase, "{} secret {}", vector.language, vector.secret);
+        let (decoded, found) = from_words(vector.phrase).unwrap();
+        assert_eq!(decoded, secret);
+        assert_eq!(found.name, vector.language);
+        assert_eq!(decoded.address(Network::Mainnet).to_string(), vector.address);
+    }
+}
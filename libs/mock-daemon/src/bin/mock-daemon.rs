@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+
+use mock_daemon::{Config, Event, MockDaemon};
+
+#[derive(Parser)]
+#[clap(name = "mock-daemon")]
+#[clap(about = "A scriptable DERO getwork daemon for testing miners", long_about = None)]
+struct Cli {
+    #[clap(short, long, default_value_t = String::from("127.0.0.1:10100"))]
+    /// Address to serve /ws/<wallet> on.
+    listen: String,
+    #[clap(short, long, default_value_t = 1)]
+    difficulty: u64,
+    #[clap(short, long, default_value_t = 1000)]
+    /// Milliseconds between synthetic jobs.
+    interval: u64,
+    #[clap(short, long)]
+    /// JSON file with a list of events to play before synthetic jobs.
+    script: Option<PathBuf>,
+    #[clap(long)]
+    /// Stop sending jobs once the script ran out.
+    no_synthetic: bool,
+    #[clap(long, default_value_t = String::from("Mainnet"))]
+    /// Network reported to DERO.GetInfo, Mainnet, Testnet or Simulator.
+    network: String,
+    #[clap(long, default_value_t = String::from("127.0.0.1:10102"))]
+    /// Address to serve JSON-RPC on.
+    rpc_listen: String,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let script: Vec<Event> = match &cli.script {
+        Some(path) => {
+            let data = fs::read_to_string(path).expect("unable to read script");
+            serde_json::from_str(&data).expect("invalid script")
+        }
+        None => vec![],
+    };
+    let daemon = MockDaemon::start(&cli.listen, Config {
+        difficulty: cli.difficulty,
+        job_interval: Duration::from_millis(cli.interval),
+        script,
+        synthetic: !cli.no_synthetic,
+        network: cli.network,
+        rpc_listen: cli.rpc_listen,
+    }).expect("unable to listen");
+    println!("Serving getwork on {} and JSON-RPC on {}", daemon.url(), daemon.rpc_url());
+    daemon.join();
+}
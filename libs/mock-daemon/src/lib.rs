@@ -0,0 +1,384 @@
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use websocket::message::CloseData;
+use websocket::OwnedMessage;
+use websocket::result::WebSocketError;
+use websocket::sync::{Client, Server};
+
+use derohe::block::{self, check_pow_hash};
+use derohe::pow::astrobwt;
+use derohe::rpc::daemon_rpc::{GetBlockTemplateResult, GetInfoResult, SubmitBlockParams};
+
+/// Bytes at the end of a miniblock the miner is free to change, nonce and thread id.
+const NONCE_SIZE: usize = 5;
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// A scripted step, played in order across connections before synthetic jobs take over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// Send this exact job.
+    Job(GetBlockTemplateResult),
+    /// Send a generated job at the configured difficulty.
+    Synthetic,
+    /// Send a raw text frame, e.g. a payload the miner can't decode.
+    Raw { text: String },
+    /// Send a close frame and drop the connection.
+    Close { code: u16, reason: String },
+    /// Drop the connection without a close frame.
+    Disconnect,
+    /// Keep serving the connection without sending jobs.
+    Wait { millis: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub difficulty: u64,
+    pub job_interval: Duration,
+    pub script: Vec<Event>,
+    /// Send a synthetic job every `job_interval` once the script ran out.
+    pub synthetic: bool,
+    /// What `DERO.GetInfo` reports, `Mainnet`, `Testnet` or `Simulator` like the Go daemon.
+    pub network: String,
+    /// Address to serve JSON-RPC on, apart from getwork like the Go daemon.
+    pub rpc_listen: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            difficulty: 1,
+            job_interval: Duration::from_secs(1),
+            script: vec![],
+            synthetic: true,
+            network: String::from("Mainnet"),
+            rpc_listen: String::from("127.0.0.1:0"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub wallet_address: String,
+    pub job_id: String,
+    pub blob: String,
+    /// Why the miniblock was rejected, `None` when it was accepted.
+    pub error: Option<String>,
+}
+
+impl Submission {
+    pub fn accepted(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    config: Config,
+    script: Mutex<VecDeque<Event>>,
+    jobs: Mutex<HashMap<String, GetBlockTemplateResult>>,
+    submissions: Mutex<Vec<Submission>>,
+    connections: AtomicUsize,
+    counter: AtomicU64,
+}
+
+impl State {
+    fn synthetic_job(&self) -> GetBlockTemplateResult {
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst);
+        let epoch_milli = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let mut blob = [0u8; block::MINIBLOCK_SIZE];
+        blob[0] = 0x41;
+        let filler = astrobwt::sha3(&counter.to_le_bytes());
+        blob[1..33].copy_from_slice(&filler);
+        GetBlockTemplateResult {
+            JobID: format!("{}.{}.mock", epoch_milli, counter),
+            Blockhashing_blob: hex::encode(blob),
+            Difficulty: self.config.difficulty.to_string(),
+            Difficultyuint64: self.config.difficulty,
+            Height: 100 + counter,
+            Prev_Hash: hex::encode(filler),
+            EpochMilli: epoch_milli,
+            Status: String::from("OK"),
+            ..Default::default()
+        }
+    }
+
+    fn info(&self) -> GetInfoResult {
+        GetInfoResult {
+            Height: 100 + self.counter.load(Ordering::SeqCst) as i64,
+            Network: self.config.network.clone(),
+            Testnet: self.config.network != "Mainnet",
+            Status: String::from("OK"),
+            ..Default::default()
+        }
+    }
+
+    fn validate(&self, params: &SubmitBlockParams) -> Result<(), String> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(&params.JobID).ok_or_else(|| format!("unknown job {}", params.JobID))?;
+        let blob = hex::decode(&params.MiniBlockhashing_blob).map_err(|e| format!("invalid miniblock: {}", e))?;
+        if blob.len() != block::MINIBLOCK_SIZE {
+            return Err(format!("invalid miniblock length: {}", blob.len()));
+        }
+        let template = hex::decode(&job.Blockhashing_blob).map_err(|e| format!("invalid job blob: {}", e))?;
+        if template.len() != block::MINIBLOCK_SIZE || blob[..block::MINIBLOCK_SIZE - NONCE_SIZE] != template[..block::MINIBLOCK_SIZE - NONCE_SIZE] {
+            return Err(String::from("miniblock doesn't match job"));
+        }
+        let pow_hash = astrobwt::pow16(&blob);
+        if !check_pow_hash(pow_hash, job.Difficultyuint64) {
+            return Err(String::from("miniblock has low difficulty"));
+        }
+        Ok(())
+    }
+}
+
+/// A getwork daemon on localhost serving `/ws/<wallet>` without TLS.
+/// Every request on its JSON-RPC port is answered as `DERO.GetInfo`, the one call the miner makes in websocket mode.
+#[derive(Debug)]
+pub struct MockDaemon {
+    address: SocketAddr,
+    rpc_address: SocketAddr,
+    state: Arc<State>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl MockDaemon {
+    pub fn start(listen: &str, config: Config) -> io::Result<Self> {
+        let mut server = Server::bind(listen)?;
+        let address = server.local_addr()?;
+        let rpc = TcpListener::bind(&config.rpc_listen)?;
+        let rpc_address = rpc.local_addr()?;
+        let state = Arc::new(State {
+            script: Mutex::new(config.script.iter().cloned().collect()),
+            config,
+            jobs: Mutex::new(HashMap::new()),
+            submissions: Mutex::new(vec![]),
+            connections: AtomicUsize::new(0),
+            counter: AtomicU64::new(0),
+        });
+        let rpc_state = state.clone();
+        thread::spawn(move || {
+            for stream in rpc.incoming().flatten() {
+                let _ = answer_info(stream, &rpc_state);
+            }
+        });
+        let server_state = state.clone();
+        let handle = thread::spawn(move || loop {
+            let upgrade = match server.accept() {
+                Ok(upgrade) => upgrade,
+                Err(_) => continue
+            };
+            let uri = upgrade.uri();
+            let wallet_address = match uri.strip_prefix("/ws/") {
+                Some(wallet_address) if !wallet_address.is_empty() => wallet_address.to_string(),
+                _ => {
+                    let _ = upgrade.reject();
+                    continue;
+                }
+            };
+            let client = match upgrade.accept() {
+                Ok(client) => client,
+                Err(_) => continue
+            };
+            server_state.connections.fetch_add(1, Ordering::SeqCst);
+            let state = server_state.clone();
+            thread::spawn(move || {
+                let _ = Connection { state, client, wallet_address }.serve();
+            });
+        });
+        Ok(MockDaemon { address, rpc_address, state, handle })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Address the miner should be pointed at.
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.address)
+    }
+
+    pub fn rpc_address(&self) -> SocketAddr {
+        self.rpc_address
+    }
+
+    /// Address the miner should check the daemon's network on.
+    pub fn rpc_url(&self) -> String {
+        format!("http://{}", self.rpc_address)
+    }
+
+    pub fn connections(&self) -> usize {
+        self.state.connections.load(Ordering::SeqCst)
+    }
+
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.state.submissions.lock().unwrap().clone()
+    }
+
+    /// Polls `condition` until it holds or `timeout` passes.
+    pub fn wait_for<F: Fn(&MockDaemon) -> bool>(&self, timeout: Duration, condition: F) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if condition(self) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        condition(self)
+    }
+
+    /// Serves until the process exits.
+    pub fn join(self) {
+        let _ = self.handle.join();
+    }
+}
+
+fn answer_info(mut stream: TcpStream, state: &State) -> io::Result<()> {
+    let body = serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": state.info()}).to_string();
+    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)?;
+    stream.shutdown(Shutdown::Write)?;
+    // closing with the request body unread would reset the connection before the client reads the answer
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let _ = io::copy(&mut stream.take(1 << 16), &mut io::sink());
+    Ok(())
+}
+
+struct Connection {
+    state: Arc<State>,
+    client: Client<TcpStream>,
+    wallet_address: String,
+}
+
+impl Connection {
+    fn serve(mut self) -> Result<(), WebSocketError> {
+        self.client.stream_ref().set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut last_job: Option<GetBlockTemplateResult> = None;
+        let mut last_sent = Instant::now();
+        loop {
+            let event = self.state.script.lock().unwrap().pop_front();
+            match event {
+                Some(Event::Job(job)) => last_job = Some(self.send_job(job)?),
+                Some(Event::Synthetic) => last_job = Some(self.send_job(self.state.synthetic_job())?),
+                Some(Event::Raw { text }) => self.client.send_message(&OwnedMessage::Text(text))?,
+                Some(Event::Close { code, reason }) => {
+                    let _ = self.client.send_message(&OwnedMessage::Close(Some(CloseData::new(code, reason))));
+                    return Ok(self.client.shutdown()?);
+                }
+                Some(Event::Disconnect) => return Ok(self.client.shutdown()?),
+                Some(Event::Wait { millis }) => {
+                    let start = Instant::now();
+                    while start.elapsed() < Duration::from_millis(millis) {
+                        if !self.receive(&mut last_job)? {
+                            return Ok(());
+                        }
+                    }
+                }
+                None => {
+                    if self.state.config.synthetic && (last_job.is_none() || last_sent.elapsed() >= self.state.config.job_interval) {
+                        last_job = Some(self.send_job(self.state.synthetic_job())?);
+                        last_sent = Instant::now();
+                    }
+                    if !self.receive(&mut last_job)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn send_job(&mut self, job: GetBlockTemplateResult) -> Result<GetBlockTemplateResult, WebSocketError> {
+        self.state.jobs.lock().unwrap().insert(job.JobID.clone(), job.clone());
+        let text = serde_json::to_string(&job).unwrap();
+        self.client.send_message(&OwnedMessage::Text(text))?;
+        Ok(job)
+    }
+
+    /// Handles one incoming frame, returns false once the miner is gone.
+    fn receive(&mut self, last_job: &mut Option<GetBlockTemplateResult>) -> Result<bool, WebSocketError> {
+        let message = match self.client.recv_message() {
+            Ok(message) => message,
+            Err(WebSocketError::IoError(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(true),
+            Err(WebSocketError::NoDataAvailable) => return Ok(false),
+            Err(e) => return Err(e)
+        };
+        match message {
+            OwnedMessage::Text(text) => {
+                let params: SubmitBlockParams = match serde_json::from_str(&text) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        self.client.send_message(&OwnedMessage::Close(Some(CloseData::new(1003, e.to_string()))))?;
+                        return Ok(false);
+                    }
+                };
+                let result = self.state.validate(&params);
+                self.state.submissions.lock().unwrap().push(Submission {
+                    wallet_address: self.wallet_address.clone(),
+                    job_id: params.JobID,
+                    blob: params.MiniBlockhashing_blob,
+                    error: result.clone().err(),
+                });
+                // like the daemon, answer with the current job and updated counters
+                if let Some(job) = last_job {
+                    match result {
+                        Ok(()) => job.MiniBlocks += 1,
+                        Err(e) => job.LastError = e,
+                    }
+                    let job = job.clone();
+                    *last_job = Some(self.send_job(job)?);
+                }
+                Ok(true)
+            }
+            OwnedMessage::Ping(data) => {
+                self.client.send_message(&OwnedMessage::Pong(data))?;
+                Ok(true)
+            }
+            OwnedMessage::Close(_) => Ok(false),
+            _ => Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use derohe::rpc::client::Client;
+    use derohe::rpc::daemon_rpc::SubmitBlockParams;
+
+    use super::{Config, Event, MockDaemon};
+
+    #[test]
+    fn validate_submission() {
+        let daemon = MockDaemon::start("127.0.0.1:0", Config::default()).unwrap();
+        let job = daemon.state.synthetic_job();
+        daemon.state.jobs.lock().unwrap().insert(job.JobID.clone(), job.clone());
+        let mut blob = hex::decode(&job.Blockhashing_blob).unwrap();
+        blob[47] = 1;
+        assert_eq!(daemon.state.validate(&SubmitBlockParams { JobID: job.JobID.clone(), MiniBlockhashing_blob: hex::encode(&blob) }), Ok(()));
+        assert!(daemon.state.validate(&SubmitBlockParams { JobID: String::from("missing"), MiniBlockhashing_blob: hex::encode(&blob) }).is_err());
+        blob[1] ^= 0xff;
+        assert!(daemon.state.validate(&SubmitBlockParams { JobID: job.JobID, MiniBlockhashing_blob: hex::encode(&blob) }).is_err());
+    }
+
+    #[test]
+    fn answers_get_info() {
+        let daemon = MockDaemon::start("127.0.0.1:0", Config { network: String::from("Testnet"), ..Config::default() }).unwrap();
+        let info = Client::new(&daemon.rpc_url()).get_info().unwrap();
+        assert_eq!(info.Network, "Testnet");
+        assert!(info.Testnet);
+    }
+
+    #[test]
+    fn script_json() {
+        let script: Vec<Event> = serde_json::from_str(r#"[{"type": "synthetic"}, {"type": "wait", "millis": 10}, {"type": "close", "code": 1008, "reason": "address not registered"}]"#).unwrap();
+        assert_eq!(script[2], Event::Close { code: 1008, reason: String::from("address not registered") });
+    }
+}
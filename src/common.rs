@@ -1,3 +1,4 @@
 pub(crate) mod definitions {
     pub const VERSION: &str = "v0.1.0";
+    pub const MINER_NAME: &str = "rustic-miner";
 }
\ No newline at end of file
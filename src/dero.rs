@@ -1,44 +1,239 @@
 use std::fmt::format;
 use std::sync::{Arc, RwLock};
-use crossbeam::channel::{bounded, Receiver, Sender};
-use log::{debug, error, info};
-use websocket::{ClientBuilder, OwnedMessage, WebSocketResult};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crossbeam::channel::{bounded, Receiver, select, Sender, tick, unbounded};
+use log::{debug, error, info, warn};
+use websocket::OwnedMessage;
+use websocket::result::WebSocketError;
 use websocket::futures::future::err;
-use websocket::native_tls::TlsConnector;
 use derohe::block;
 use derohe::pow::astrobwt;
+use derohe::pow::backend::Backend;
+use derohe::pow::memory::MemoryOptions;
 use derohe::rpc::daemon_rpc;
 use derohe::rpc::daemon_rpc::GetBlockTemplateResult;
 use crate::{AtomicCell, ShardedLock};
+use crate::error::{JobError, MinerError};
+use crate::http::TemplatePoller;
 
 pub mod address {
-    use log::debug;
+    use log::info;
 
     use derohe::rpc;
+    use derohe::rpc::address::{Address, Network, ReversedG1};
+    use derohe::rpc::client::Client;
+    use derohe::rpc::daemon_rpc::GetInfoResult;
+    use derohe::rpc::rpc::{Argument, Arguments, Value, RPC_COMMENT, RPC_DESTINATION_PORT, RPC_VALUE_TRANSFER};
+
+    use crate::error::MinerError;
 
     pub fn validate(s: &str) -> Result<(), String> {
         match rpc::address::Address::from_string(s) {
             Ok(_) => Ok(()),
-            Err(e) => return Err(format!("{}{}", "invalid address, reason: ", e))
+            Err(e) => Err(MinerError::from(e).to_string())
+        }
+    }
+
+    /// What `address inspect` prints, one field per line and one line per integrated argument.
+    pub fn describe(address: &Address) -> String {
+        let mut lines = vec![
+            format!("network: {}", address.network()),
+            format!("kind: {}", address.kind()),
+            format!("public key: {}", hex::encode(address.public_key().to_compressed_reversed_sign())),
+        ];
+        for argument in address.arguments().iter() {
+            lines.push(format!("argument {} ({:?}): {}", argument.name(), argument.data_type(), argument.value()));
+        }
+        lines.join("\n")
+    }
+
+    /// Adds the standard wallet arguments to `address`, each replacing an argument of the same name and type.
+    pub fn integrate(address: Address, port: Option<u64>, comment: Option<String>, value: Option<u64>) -> Address {
+        let added: Vec<Argument> = [
+            port.map(|port| Argument::new(RPC_DESTINATION_PORT, Value::Uint64(port))),
+            comment.map(|comment| Argument::new(RPC_COMMENT, Value::String(comment))),
+            value.map(|value| Argument::new(RPC_VALUE_TRANSFER, Value::Uint64(value))),
+        ].into_iter().flatten().collect();
+        let mut arguments: Vec<Argument> = address.arguments().iter()
+            .filter(|argument| !added.iter().any(|new| new.name() == argument.name() && new.data_type() == argument.data_type()))
+            .cloned()
+            .collect();
+        arguments.extend(added);
+        address.with_arguments(Arguments::new(arguments))
+    }
+
+    /// Moves `address` to `network`, or to the other prefix when none is given.
+    pub fn convert(address: Address, network: Option<Network>) -> Address {
+        let network = network.unwrap_or(if address.network().is_mainnet() { Network::Testnet } else { Network::Mainnet });
+        address.with_network(network)
+    }
+
+    /// Port the Go daemon serves JSON-RPC on, getwork is served apart on 10100.
+    pub const JSON_RPC_PORT: u16 = 10102;
+
+    /// The daemon's JSON-RPC url on the host of a getwork address, JSON-RPC is plain http whatever getwork uses.
+    pub fn json_rpc_address(daemon_address: &str) -> String {
+        let rest = daemon_address.split_once("://").map_or(daemon_address, |(_, rest)| rest);
+        let authority = rest.split('/').next().unwrap_or(rest);
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => host,
+            _ => authority,
+        };
+        format!("http://{}:{}", host, JSON_RPC_PORT)
+    }
+
+    /// Older daemons leave `network` empty and only set `testnet`.
+    pub fn daemon_network(info: &GetInfoResult) -> Network {
+        info.Network.parse().unwrap_or(if info.Testnet { Network::Testnet } else { Network::Mainnet })
+    }
+
+    /// Refuses to mine for a wallet the daemon's network can't pay, asking `DERO.GetInfo` on `json_rpc_address`.
+    pub fn check_network(wallet: &Address, json_rpc_address: &str) -> Result<(), MinerError> {
+        let client = Client::new(json_rpc_address);
+        let info = client.get_info().map_err(|error| MinerError::NetworkUnknown { url: client.url().to_string(), error })?;
+        let daemon = daemon_network(&info);
+        if !wallet.network().is_compatible(daemon) {
+            return Err(MinerError::NetworkMismatch { wallet: wallet.network(), daemon });
         }
+        info!("Daemon is on {}", daemon);
+        Ok(())
     }
 }
 
 pub mod difficulty {
     pub fn check_pow_hash(pow_hash: [u8; 32], difficulty: u64) -> bool {
-        let big_pow_hash = num_bigint::BigInt::from_bytes_le(num_bigint::Sign::Plus, &pow_hash);
-        let big_difficultly = num_bigint::BigInt::from(difficulty);
-        if big_pow_hash <= big_difficultly {
-            return true;
-        }
-        return false;
+        derohe::block::check_pow_hash(pow_hash, difficulty)
     }
 }
 
+/// Difference between the daemon's `EpochMilli` and our clock that is worth warning about.
+pub const MAX_CLOCK_SKEW_MILLIS: i64 = 2000;
+
 #[derive(Debug)]
 pub struct Job {
     pub(crate) ijob: daemon_rpc::GetBlockTemplateResult,
     pub(crate) count: u64,
+    pub(crate) paused: bool,
+    /// When the current job id was first seen.
+    pub(crate) received: Instant,
+    /// Local time minus the daemon's `EpochMilli`, in milliseconds.
+    pub(crate) clock_skew: i64,
+}
+
+impl Default for Job {
+    fn default() -> Self {
+        Job {
+            ijob: daemon_rpc::GetBlockTemplateResult::default(),
+            count: 0,
+            paused: false,
+            received: Instant::now(),
+            clock_skew: 0,
+        }
+    }
+}
+
+impl Job {
+    pub fn age(&self) -> Duration {
+        self.received.elapsed()
+    }
+
+    /// Stops miners from hashing the current job until a new one arrives.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.count += 1;
+    }
+}
+
+pub fn validate_job(job: &daemon_rpc::GetBlockTemplateResult) -> Result<(), JobError> {
+    let blob = hex::decode(&job.Blockhashing_blob).map_err(JobError::InvalidBlob)?;
+    if blob.len() != block::MINIBLOCK_SIZE {
+        return Err(JobError::InvalidLength(blob.len()));
+    }
+    if blob[0] & 0xf != 1 {
+        return Err(JobError::UnknownVersion(blob[0] & 0xf));
+    }
+    Ok(())
+}
+
+/// A miniblock that meets the job difficulty, waiting to be submitted to the daemon.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub job_id: String,
+    pub blob: [u8; block::MINIBLOCK_SIZE],
+}
+
+impl Solution {
+    pub fn params(&self) -> daemon_rpc::SubmitBlockParams {
+        daemon_rpc::SubmitBlockParams {
+            JobID: self.job_id.clone(),
+            MiniBlockhashing_blob: hex::encode(self.blob),
+        }
+    }
+}
+
+/// Hands a new job to the miner threads, they drop whatever they were hashing.
+pub fn publish_job(job: &RwLock<Job>, template: daemon_rpc::GetBlockTemplateResult) -> Result<(), JobError> {
+    validate_job(&template)?;
+    let now_milli = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+    let mut njob = job.write().unwrap();
+    let notices = inspect_job(&njob, &template, now_milli);
+    let mut duplicate = false;
+    for notice in &notices {
+        match notice {
+            JobNotice::Duplicate => {
+                debug!("Job {} was already received", template.JobID);
+                duplicate = true;
+            }
+            JobNotice::HeightRegression { from, to } => warn!("Daemon height went backwards from {} to {}", from, to),
+            JobNotice::ClockSkew(skew) => {
+                if njob.clock_skew.abs() <= MAX_CLOCK_SKEW_MILLIS {
+                    warn!("Local clock is {} ms off the daemon, miniblocks may be rejected, please sync your clock", skew);
+                }
+            }
+        }
+    }
+    let clock_skew = now_milli as i64 - template.EpochMilli as i64;
+    if duplicate {
+        // same work with updated counters, restarting the miners would gain nothing
+        njob.ijob = template;
+        njob.clock_skew = clock_skew;
+        return Ok(());
+    }
+    *njob = Job {
+        ijob: template,
+        count: njob.count + 1,
+        paused: false,
+        received: Instant::now(),
+        clock_skew,
+    };
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JobNotice {
+    /// The job id is the one we are already mining.
+    Duplicate,
+    HeightRegression { from: u64, to: u64 },
+    /// Local time minus the daemon's `EpochMilli` exceeds `MAX_CLOCK_SKEW_MILLIS`.
+    ClockSkew(i64),
+}
+
+pub fn inspect_job(previous: &Job, template: &daemon_rpc::GetBlockTemplateResult, now_milli: u64) -> Vec<JobNotice> {
+    let mut notices = vec![];
+    if previous.count > 0 {
+        if previous.ijob.JobID == template.JobID {
+            notices.push(JobNotice::Duplicate);
+        }
+        if template.Height < previous.ijob.Height {
+            notices.push(JobNotice::HeightRegression { from: previous.ijob.Height, to: template.Height });
+        }
+    }
+    let skew = now_milli as i64 - template.EpochMilli as i64;
+    if template.EpochMilli != 0 && skew.abs() > MAX_CLOCK_SKEW_MILLIS {
+        notices.push(JobNotice::ClockSkew(skew));
+    }
+    notices
 }
 
 #[derive(Debug)]
@@ -46,14 +241,20 @@ pub struct Miner {
     id: usize,
     job: Arc<RwLock<Job>>,
     counter: Arc<AtomicCell<i64>>,
+    solutions: Sender<Solution>,
+    backend: Backend,
+    memory: MemoryOptions,
 }
 
 impl Miner {
-    pub fn new(id: usize, job: Arc<RwLock<Job>>, counter: Arc<AtomicCell<i64>>) -> Miner {
+    pub fn new(id: usize, job: Arc<RwLock<Job>>, counter: Arc<AtomicCell<i64>>, solutions: Sender<Solution>, backend: Backend, memory: MemoryOptions) -> Miner {
         Miner {
             id,
             job,
             counter,
+            solutions,
+            backend,
+            memory,
         }
     }
 
@@ -61,17 +262,27 @@ impl Miner {
         let mut local_job: daemon_rpc::GetBlockTemplateResult;
         let mut local_job_count: u64;
         let mut real_job_count: u64;
+        let mut paused: bool;
         let mut work: [u8; block::MINIBLOCK_SIZE] = [(0 as u8); block::MINIBLOCK_SIZE];
         let mut diff: u64;
         let mut i: u32 = 0;
+        let mut context = astrobwt::Pow16Context::with_memory(self.backend, self.memory);
+        let placement = context.placement();
+        debug!("Miner {} scratch memory: {:?}", self.id, placement);
+        // every thread asks the same, one warning is enough
+        if self.id == 1 && !placement.satisfies(self.memory) {
+            warn!("Scratch memory options were only partly granted, got {:?}", placement);
+        }
         loop {
             {
                 let job = self.job.read().unwrap();
                 local_job = job.ijob.clone();
                 local_job_count = job.count;
                 real_job_count = job.count;
+                paused = job.paused;
             }
-            if local_job_count == 0 {
+            if local_job_count == 0 || paused {
+                thread::sleep(IDLE_DELAY);
                 continue;
             }
             // info!("thread {}, {}", self.id, local_job.JobID);
@@ -100,19 +311,18 @@ impl Miner {
                 error!("Unknown version, please check for updates, version={}", work[0]&0xf);
                 continue;
             }
-
             while real_job_count == local_job_count {
-                i = i + 1;
+                i += 1;
                 work[block::MINIBLOCK_SIZE - 5] = (i >> 24) as u8;
                 work[block::MINIBLOCK_SIZE - 4] = (i >> 16) as u8;
                 work[block::MINIBLOCK_SIZE - 3] = (i >> 8) as u8;
                 work[block::MINIBLOCK_SIZE - 2] = (i) as u8;
 
-                let powhash = astrobwt::pow16(work.as_ref());
+                let powhash = context.hash(&work);
                 self.counter.fetch_add(1);
-                if difficulty::check_pow_hash(powhash, diff) == true {
+                if difficulty::check_pow_hash(powhash, diff) {
                     info!("Succecssfully found DERO Miniblock, difficulty={}, height={}", diff, local_job.Height);
-                    // TODO: submit job
+                    let _ = self.solutions.send(Solution { job_id: local_job.JobID.clone(), blob: work });
                 }
                 {
                     let job = self.job.read().unwrap();
@@ -124,21 +334,160 @@ impl Miner {
     }
 }
 
-pub enum MinerError {
-    WebSocketError(String)
+pub mod close {
+    pub const UNSUPPORTED_DATA: u16 = 1003;
+    pub const INVALID_PAYLOAD: u16 = 1007;
+    pub const POLICY_VIOLATION: u16 = 1008;
+
+    /// Reasons the daemon gives when it will never accept our work, reconnecting won't help.
+    const FATAL_REASONS: [&str; 3] = ["not registered", "unregistered", "invalid address"];
+
+    pub fn should_reconnect(code: Option<u16>, reason: &str) -> bool {
+        let reason = reason.to_lowercase();
+        if FATAL_REASONS.iter().any(|fatal| reason.contains(fatal)) {
+            return false;
+        }
+        !matches!(code, Some(UNSUPPORTED_DATA | INVALID_PAYLOAD | POLICY_VIOLATION))
+    }
 }
 
-#[derive(Debug)]
+/// Daemon connections split into a reader for a thread of its own and a writer for the session.
+mod stream {
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::net::{Shutdown, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use websocket::{ClientBuilder, WebSocketError};
+    use websocket::native_tls::{HandshakeError, TlsConnector, TlsStream};
+    use websocket::result::WSUrlErrorKind;
+    use websocket::stream::sync::ReadWritePair;
+    use websocket::sync::Client;
+    use websocket::url::{ParseError, Url};
+
+    use crate::error::MinerError;
+
+    /// How long a TLS read holds the stream waiting for data before the writer gets a turn.
+    const TLS_READ_SLICE: Duration = Duration::from_millis(20);
+
+    pub type Halves = ReadWritePair<Box<dyn Read + Send>, Box<dyn Write + Send>>;
+
+    pub struct Connection {
+        pub client: Client<Halves>,
+        /// Shuts the socket down once dropped, which ends a blocked reader.
+        pub shutdown: ShutdownOnDrop,
+    }
+
+    pub struct ShutdownOnDrop(TcpStream);
+
+    impl Drop for ShutdownOnDrop {
+        fn drop(&mut self) {
+            let _ = self.0.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// A TLS stream can't be read and written from two threads at once, both halves share it.
+    #[derive(Clone)]
+    struct SharedTls(Arc<Mutex<TlsStream<TcpStream>>>);
+
+    impl Read for SharedTls {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            loop {
+                // the socket timeout only hands the stream to the writer now and then, TLS resumes a cut record and
+                // the websocket reader above never sees it
+                // bound first so the lock is released before yielding
+                let result = self.0.lock().unwrap().read(buf);
+                match result {
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => thread::yield_now(),
+                    result => return result,
+                }
+            }
+        }
+    }
+
+    impl Write for SharedTls {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.lock().unwrap().flush()
+        }
+    }
+
+    /// Opens a `ws://` or `wss://` connection, certificates aren't checked as daemons use self signed ones.
+    pub fn connect(address: &str) -> Result<Connection, MinerError> {
+        let mut builder = ClientBuilder::new(address)?;
+        let url = Url::parse(address)?;
+        let host = url.host_str().ok_or(ParseError::EmptyHost)?;
+        let port = url.port_or_known_default().ok_or(ParseError::InvalidPort)?;
+        let tcp = TcpStream::connect((host, port)).map_err(WebSocketError::IoError)?;
+        let shutdown = ShutdownOnDrop(tcp.try_clone().map_err(WebSocketError::IoError)?);
+        let halves: Halves = match url.scheme() {
+            "ws" => ReadWritePair(Box::new(tcp.try_clone().map_err(WebSocketError::IoError)?), Box::new(tcp)),
+            "wss" => {
+                let connector = TlsConnector::builder().danger_accept_invalid_certs(true).build()?;
+                let tls = connector.connect(host, tcp).map_err(|e| match e {
+                    HandshakeError::Failure(e) => MinerError::from(e),
+                    HandshakeError::WouldBlock(_) => unreachable!("the socket blocks; qed"),
+                })?;
+                tls.get_ref().set_read_timeout(Some(TLS_READ_SLICE)).map_err(WebSocketError::IoError)?;
+                let tls = SharedTls(Arc::new(Mutex::new(tls)));
+                ReadWritePair(Box::new(tls.clone()), Box::new(tls))
+            }
+            _ => return Err(WebSocketError::Other(Box::new(WSUrlErrorKind::InvalidScheme)).into()),
+        };
+        Ok(Connection { client: builder.connect_on(halves)?, shutdown })
+    }
+}
+
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+const IDLE_DELAY: Duration = Duration::from_millis(100);
+
+/// Where jobs come from, both variants feed the same miner threads.
+#[derive(Debug, Clone)]
+pub enum WorkSource {
+    WebSocket(WorkGatherer),
+    Http(TemplatePoller),
+}
+
+impl WorkSource {
+    pub fn receiver(&self) -> Receiver<MinerError> {
+        match self {
+            WorkSource::WebSocket(wg) => wg.receiver(),
+            WorkSource::Http(poller) => poller.receiver(),
+        }
+    }
+
+    pub fn latency(&self) -> Arc<AtomicCell<Option<Duration>>> {
+        match self {
+            WorkSource::WebSocket(wg) => wg.latency(),
+            WorkSource::Http(poller) => poller.latency(),
+        }
+    }
+
+    pub fn get_work(self) {
+        match self {
+            WorkSource::WebSocket(wg) => wg.get_work(),
+            WorkSource::Http(poller) => poller.get_work(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct WorkGatherer {
     wallet_address: String,
     daemon_rpc_address: String,
     sender: Sender<MinerError>,
     receiver: Receiver<MinerError>,
     job: Arc<RwLock<Job>>,
+    latency: Arc<AtomicCell<Option<Duration>>>,
+    solutions: Receiver<Solution>,
 }
 
 impl WorkGatherer {
-    pub fn new(wallet_address: String, daemon_rpc_address: String, job: Arc<RwLock<Job>>) -> Self {
+    pub fn new(wallet_address: String, daemon_rpc_address: String, job: Arc<RwLock<Job>>, solutions: Receiver<Solution>) -> Self {
         let (sender, receiver) = bounded(10);
         WorkGatherer {
             wallet_address,
@@ -146,6 +495,8 @@ impl WorkGatherer {
             sender,
             receiver,
             job,
+            latency: Arc::new(AtomicCell::new(None)),
+            solutions,
         }
     }
 
@@ -153,45 +504,220 @@ impl WorkGatherer {
         self.receiver.clone()
     }
 
+    /// Round trip time of the last ping answered by the daemon.
+    pub fn latency(&self) -> Arc<AtomicCell<Option<Duration>>> {
+        self.latency.clone()
+    }
+
+    /// Runs a single connection to the daemon and reports why it ended, reconnecting is up to the receiver.
     pub fn get_work(self) {
-        let address = format!("wss://{}/ws/{}", self.daemon_rpc_address, self.wallet_address);
+        if let Err(e) = self.session() {
+            let _ = self.sender.send(e);
+        }
+    }
+
+    fn session(&self) -> Result<(), MinerError> {
+        let address = if self.daemon_rpc_address.contains("://") {
+            format!("{}/ws/{}", self.daemon_rpc_address.trim_end_matches('/'), self.wallet_address)
+        } else {
+            format!("wss://{}/ws/{}", self.daemon_rpc_address, self.wallet_address)
+        };
         info!("Connecting to {}", address);
-        let mut client_b = ClientBuilder::new(&*address)
-            .unwrap();
-        let response_client_connect = client_b.connect_secure(Option::from(TlsConnector::builder().danger_accept_invalid_certs(true).build().unwrap()));
-
-        let mut client = match response_client_connect {
-            Ok(client) => client,
-            Err(e) => {
-                self.sender.send(MinerError::WebSocketError(format!("{}", e)));
-                return;
+        let connection = stream::connect(&address)?;
+        let (mut reader, mut writer) = connection.client.split().map_err(WebSocketError::IoError)?;
+        // a blocking read that only ever returns whole messages, the session ends when the socket is shut down
+        let (messages_sender, messages) = unbounded();
+        thread::spawn(move || {
+            loop {
+                let message = reader.recv_message();
+                let failed = message.is_err();
+                if messages_sender.send(message).is_err() || failed {
+                    return;
+                }
             }
-        };
+        });
+        let _shutdown = connection.shutdown;
 
+        let pings = tick(PING_INTERVAL);
+        let mut ping_payload: u64 = 0;
+        let mut ping_sent: Option<(u64, Instant)> = None;
         loop {
-            let response = client.recv_message();
-            let message = match response {
-                Ok(o) => match o {
-                    OwnedMessage::Text(val) => val,
-                    _ => {
-                        self.sender.send(MinerError::WebSocketError(String::from("Received wrong type")));
-                        return;
-                    }
-                },
-                Err(e) => {
-                    self.sender.send(MinerError::WebSocketError(format!("{}", e)));
-                    return;
+            let message = select! {
+                recv(messages) -> message => message.expect("the reader reports its error before stopping; qed")?,
+                recv(self.solutions) -> solution => {
+                    let solution = match solution {
+                        Ok(solution) => solution,
+                        Err(_) => return Ok(())
+                    };
+                    info!("Submitting miniblock for job {}", solution.job_id);
+                    let params = serde_json::to_string(&solution.params())?;
+                    writer.send_message(&OwnedMessage::Text(params))?;
+                    continue;
+                }
+                recv(pings) -> _ => {
+                    ping_payload += 1;
+                    writer.send_message(&OwnedMessage::Ping(ping_payload.to_be_bytes().to_vec()))?;
+                    ping_sent = Some((ping_payload, Instant::now()));
+                    continue;
                 }
             };
-            let job: daemon_rpc::GetBlockTemplateResult = serde_json::from_str(&*message).unwrap();
-            debug!("{:#?}", job);
-            {
-                let mut njob = self.job.write().unwrap();
-                *njob = Job {
-                    ijob: job,
-                    count: njob.count + 1,
+            let message = match message {
+                OwnedMessage::Text(val) => val,
+                OwnedMessage::Ping(data) => {
+                    writer.send_message(&OwnedMessage::Pong(data))?;
+                    continue;
+                }
+                OwnedMessage::Pong(data) => {
+                    match ping_sent {
+                        Some((payload, sent)) if data == payload.to_be_bytes() => {
+                            let latency = sent.elapsed();
+                            debug!("Daemon latency {:?}", latency);
+                            self.latency.store(Some(latency));
+                            ping_sent = None;
+                        }
+                        _ => debug!("Ignoring unsolicited pong")
+                    }
+                    continue;
                 }
+                OwnedMessage::Binary(data) => {
+                    warn!("Ignoring binary message of {} bytes", data.len());
+                    continue;
+                }
+                OwnedMessage::Close(data) => {
+                    let _ = writer.send_message(&OwnedMessage::Close(None));
+                    return Err(match data {
+                        Some(data) => MinerError::Closed { code: Some(data.status_code), reason: data.reason },
+                        None => MinerError::Closed { code: None, reason: String::new() },
+                    });
+                }
+            };
+            match self.update_job(&message) {
+                Ok(()) => (),
+                Err(e) => { let _ = self.sender.send(e); }
             }
         }
     }
-}
\ No newline at end of file
+
+    fn update_job(&self, message: &str) -> Result<(), MinerError> {
+        let job: daemon_rpc::GetBlockTemplateResult = serde_json::from_str(message)?;
+        debug!("{:#?}", job);
+        publish_job(&self.job, job)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use derohe::rpc::address::Network;
+    use derohe::rpc::daemon_rpc::GetInfoResult;
+    use derohe::rpc::rpc::{DataType, Value};
+    use rstest::rstest;
+
+    use crate::error::{MinerError, Recovery, exit_code};
+
+    use super::{close, inspect_job, Job, JobNotice};
+    use super::address::{convert, daemon_network, describe, integrate, json_rpc_address};
+
+    #[rstest]
+    #[case(None, "", true)]
+    #[case(Some(1000), "", true)]
+    #[case(Some(1001), "daemon shutting down", true)]
+    #[case(Some(close::POLICY_VIOLATION), "", false)]
+    #[case(Some(1000), "Address not registered", false)]
+    fn close_reconnect(#[case] code: Option<u16>, #[case] reason: &str, #[case] expected: bool) {
+        assert_eq!(close::should_reconnect(code, reason), expected);
+    }
+
+    #[rstest]
+    #[case(r#"{"jobid": 1}"#, Recovery::Pause)]
+    #[case(r#"not json"#, Recovery::Pause)]
+    fn bad_payload_pauses(#[case] message: &str, #[case] expected: Recovery) {
+        let err = MinerError::from(serde_json::from_str::<derohe::rpc::daemon_rpc::GetBlockTemplateResult>(message).unwrap_err());
+        assert_eq!(err.recovery(), expected);
+    }
+
+    #[rstest]
+    #[case("zz", false)]
+    #[case("41", false)]
+    #[case("415a9e0000008386b3fe9a18000000004c2d828f0583a86db9639d36548f817100000000deb3465e1d316f0000000201", true)]
+    #[case("425a9e0000008386b3fe9a18000000004c2d828f0583a86db9639d36548f817100000000deb3465e1d316f0000000201", false)]
+    fn job_validation(#[case] blob: &str, #[case] valid: bool) {
+        let job = derohe::rpc::daemon_rpc::GetBlockTemplateResult {
+            Blockhashing_blob: blob.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(super::validate_job(&job).is_ok(), valid);
+    }
+
+    #[test]
+    fn job_notices() {
+        let template = derohe::rpc::daemon_rpc::GetBlockTemplateResult {
+            JobID: String::from("1650000000000.0.notified"),
+            Height: 100,
+            EpochMilli: 1650000000000,
+            ..Default::default()
+        };
+        let previous = Job { ijob: template.clone(), count: 1, ..Default::default() };
+        assert_eq!(inspect_job(&Job::default(), &template, 1650000000100), vec![]);
+        assert_eq!(inspect_job(&previous, &template, 1650000000100), vec![JobNotice::Duplicate]);
+        let older = derohe::rpc::daemon_rpc::GetBlockTemplateResult { JobID: String::from("1650000000500.0.notified"), Height: 99, ..template.clone() };
+        assert_eq!(inspect_job(&previous, &older, 1650000000100), vec![JobNotice::HeightRegression { from: 100, to: 99 }]);
+        assert_eq!(inspect_job(&Job::default(), &template, 1650000005000), vec![JobNotice::ClockSkew(5000)]);
+        assert_eq!(inspect_job(&Job::default(), &template, 1649999990000), vec![JobNotice::ClockSkew(-10000)]);
+    }
+
+    #[test]
+    fn rejected_close_exits() {
+        let err = MinerError::Closed { code: Some(close::POLICY_VIOLATION), reason: String::from("unregistered miner") };
+        assert_eq!(err.recovery(), Recovery::Exit(exit_code::REJECTED));
+    }
+
+    #[rstest]
+    #[case("127.0.0.1:10100", "http://127.0.0.1:10102")]
+    #[case("ws://127.0.0.1:10100", "http://127.0.0.1:10102")]
+    #[case("wss://node:10100/", "http://node:10102")]
+    #[case("node", "http://node:10102")]
+    #[case("[::1]:10100", "http://[::1]:10102")]
+    #[case("wss://[::1]", "http://[::1]:10102")]
+    fn json_rpc_address_from_getwork(#[case] daemon_address: &str, #[case] expected: &str) {
+        assert_eq!(json_rpc_address(daemon_address), expected);
+    }
+
+    #[rstest]
+    #[case("Mainnet", false, Network::Mainnet)]
+    #[case("Simulator", true, Network::Simulator)]
+    #[case("", true, Network::Testnet)]
+    #[case("", false, Network::Mainnet)]
+    fn network_from_info(#[case] network: &str, #[case] testnet: bool, #[case] expected: Network) {
+        let info = GetInfoResult { Network: network.to_string(), Testnet: testnet, ..Default::default() };
+        assert_eq!(daemon_network(&info), expected);
+    }
+
+    const INTEGRATED: &str = "deroi1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5q9rvfp4xursv9uk7at5ypnx7u3qwf5kwgphvfz92xg98939v4g6qqpapyq2cl473";
+    const PLAIN: &str = "dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmn";
+
+    #[test]
+    fn describe_integrated() {
+        let address = INTEGRATED.parse().unwrap();
+        assert_eq!(describe(&address), "network: mainnet\nkind: integrated\n\
+            public key: 0340f86a583ad3a584f8c35304bf282a52db0147d80aa46e785b502c6b8768da00\n\
+            argument C (String): payout for rig 7\nargument D (Uint64): 1337\nargument V (Uint64): 250000");
+    }
+
+    #[test]
+    fn integrate_replaces_arguments() {
+        let address = integrate(PLAIN.parse().unwrap(), Some(1337), Some("payout for rig 7".to_string()), Some(250000));
+        assert_eq!(address.to_string(), INTEGRATED);
+        let address = integrate(address, Some(1), None, None);
+        assert_eq!(address.arguments().value("D", DataType::Uint64), Some(&Value::Uint64(1)));
+        assert_eq!(address.arguments().iter().count(), 3);
+    }
+
+    #[rstest]
+    #[case(PLAIN, None, "deto1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqu8he7m")]
+    #[case(PLAIN, Some(Network::Mainnet), PLAIN)]
+    #[case("deto1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqu8he7m", None, PLAIN)]
+    fn convert_prefix(#[case] address: &str, #[case] network: Option<Network>, #[case] expected: &str) {
+        assert_eq!(convert(address.parse().unwrap(), network).to_string(), expected);
+    }
+}
@@ -0,0 +1,228 @@
+use std::{error, fmt};
+
+use derohe::pow::backend::SelfTestError;
+use derohe::rpc::address::{AddressError, Network};
+use derohe::rpc::client::ClientError;
+use derohe::wallet::keys::KeyError;
+use derohe::wallet::mnemonic::MnemonicError;
+use websocket::native_tls;
+use websocket::result::WebSocketError;
+use websocket::url::ParseError;
+
+use crate::dero::close;
+
+pub mod exit_code {
+    pub const SETUP: i32 = 2;
+    pub const ADDRESS: i32 = 3;
+    pub const TLS: i32 = 4;
+    pub const INVALID_URL: i32 = 5;
+    pub const REJECTED: i32 = 6;
+    pub const SELF_TEST: i32 = 7;
+    pub const NETWORK: i32 = 8;
+    pub const KEY: i32 = 9;
+}
+
+/// What `start_miner` should do after the work gatherer reported an error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Recovery {
+    /// Reconnect to the daemon after a delay.
+    Retry,
+    /// Stop hashing the current job until the daemon sends a valid one.
+    Pause,
+    /// Give up and exit the process with the given code.
+    Exit(i32),
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    InvalidBlob(hex::FromHexError),
+    InvalidLength(usize),
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::InvalidBlob(e) => write!(f, "unable to decode blockwork: {}", e),
+            JobError::InvalidLength(length) => write!(f, "invalid blockwork length: {}", length),
+            JobError::UnknownVersion(version) => write!(f, "unknown version, please check for updates, version={}", version),
+        }
+    }
+}
+
+impl error::Error for JobError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            JobError::InvalidBlob(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MinerError {
+    Connection(WebSocketError),
+    Tls(native_tls::Error),
+    InvalidUrl(ParseError),
+    /// The daemon broke the websocket protocol or sent a frame we can't use.
+    Protocol(WebSocketError),
+    /// The daemon closed the connection.
+    Closed { code: Option<u16>, reason: String },
+    /// A JSON-RPC call to the daemon failed.
+    Rpc(ClientError),
+    Json(serde_json::Error),
+    InvalidJob(JobError),
+    Address(AddressError),
+    Signal(ctrlc::Error),
+    /// The pow backend hashed a known vector wrong, any work it finds would be invalid.
+    SelfTest(SelfTestError),
+    /// The wallet address can't be paid on the daemon's network.
+    NetworkMismatch { wallet: Network, daemon: Network },
+    /// The daemon's network couldn't be asked for on its JSON-RPC port.
+    NetworkUnknown { url: String, error: ClientError },
+    /// A secret key couldn't be read or written.
+    Key(KeyError),
+    Seed(MnemonicError),
+}
+
+impl MinerError {
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            MinerError::Connection(_) | MinerError::Protocol(_) | MinerError::Rpc(_) => Recovery::Retry,
+            MinerError::Closed { code, reason } => {
+                if close::should_reconnect(*code, reason) {
+                    Recovery::Retry
+                } else {
+                    Recovery::Exit(exit_code::REJECTED)
+                }
+            }
+            MinerError::Json(_) | MinerError::InvalidJob(_) => Recovery::Pause,
+            MinerError::Tls(_) => Recovery::Exit(exit_code::TLS),
+            MinerError::InvalidUrl(_) => Recovery::Exit(exit_code::INVALID_URL),
+            MinerError::Address(_) => Recovery::Exit(exit_code::ADDRESS),
+            MinerError::Signal(_) => Recovery::Exit(exit_code::SETUP),
+            MinerError::SelfTest(_) => Recovery::Exit(exit_code::SELF_TEST),
+            MinerError::NetworkMismatch { .. } | MinerError::NetworkUnknown { .. } => Recovery::Exit(exit_code::NETWORK),
+            MinerError::Key(_) | MinerError::Seed(_) => Recovery::Exit(exit_code::KEY),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self.recovery() {
+            Recovery::Exit(code) => code,
+            _ => 1
+        }
+    }
+}
+
+impl fmt::Display for MinerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MinerError::Connection(e) => write!(f, "connection to daemon failed: {}", e),
+            MinerError::Tls(e) => write!(f, "unable to setup TLS: {}", e),
+            MinerError::InvalidUrl(e) => write!(f, "invalid daemon address: {}", e),
+            MinerError::Protocol(e) => write!(f, "daemon protocol error: {}", e),
+            MinerError::Closed { code, reason } => write!(f, "daemon closed the connection, code={:?}, reason={}", code, reason),
+            MinerError::Rpc(e) => write!(f, "daemon rpc failed: {}", e),
+            MinerError::Json(e) => write!(f, "unable to decode job: {}", e),
+            MinerError::InvalidJob(e) => write!(f, "invalid job: {}", e),
+            MinerError::Address(e) => write!(f, "invalid address, reason: {}", e),
+            MinerError::Signal(e) => write!(f, "unable to set signal handler: {}", e),
+            MinerError::SelfTest(e) => write!(f, "{}", e),
+            MinerError::NetworkMismatch { wallet, daemon } => write!(f, "wallet address is for {} but the daemon runs {}", wallet, daemon),
+            MinerError::NetworkUnknown { url, error } => write!(f, "unable to get the daemon's network from {}: {}", url, error),
+            MinerError::Key(e) => write!(f, "{}", e),
+            MinerError::Seed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for MinerError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MinerError::Connection(e) | MinerError::Protocol(e) => Some(e),
+            MinerError::Tls(e) => Some(e),
+            MinerError::InvalidUrl(e) => Some(e),
+            MinerError::Closed { .. } => None,
+            MinerError::Rpc(e) => Some(e),
+            MinerError::Json(e) => Some(e),
+            MinerError::InvalidJob(e) => Some(e),
+            MinerError::Address(e) => Some(e),
+            MinerError::Signal(e) => Some(e),
+            MinerError::SelfTest(e) => Some(e),
+            MinerError::NetworkMismatch { .. } => None,
+            MinerError::NetworkUnknown { error, .. } => Some(error),
+            MinerError::Key(e) => Some(e),
+            MinerError::Seed(e) => Some(e),
+        }
+    }
+}
+
+impl From<WebSocketError> for MinerError {
+    fn from(e: WebSocketError) -> Self {
+        match e {
+            WebSocketError::ProtocolError(_) | WebSocketError::DataFrameError(_) | WebSocketError::Utf8Error(_) => MinerError::Protocol(e),
+            _ => MinerError::Connection(e)
+        }
+    }
+}
+
+impl From<native_tls::Error> for MinerError {
+    fn from(e: native_tls::Error) -> Self {
+        MinerError::Tls(e)
+    }
+}
+
+impl From<ParseError> for MinerError {
+    fn from(e: ParseError) -> Self {
+        MinerError::InvalidUrl(e)
+    }
+}
+
+impl From<ClientError> for MinerError {
+    fn from(e: ClientError) -> Self {
+        MinerError::Rpc(e)
+    }
+}
+
+impl From<serde_json::Error> for MinerError {
+    fn from(e: serde_json::Error) -> Self {
+        MinerError::Json(e)
+    }
+}
+
+impl From<JobError> for MinerError {
+    fn from(e: JobError) -> Self {
+        MinerError::InvalidJob(e)
+    }
+}
+
+impl From<AddressError> for MinerError {
+    fn from(e: AddressError) -> Self {
+        MinerError::Address(e)
+    }
+}
+
+impl From<ctrlc::Error> for MinerError {
+    fn from(e: ctrlc::Error) -> Self {
+        MinerError::Signal(e)
+    }
+}
+
+impl From<SelfTestError> for MinerError {
+    fn from(e: SelfTestError) -> Self {
+        MinerError::SelfTest(e)
+    }
+}
+
+impl From<KeyError> for MinerError {
+    fn from(e: KeyError) -> Self {
+        MinerError::Key(e)
+    }
+}
+
+impl From<MnemonicError> for MinerError {
+    fn from(e: MnemonicError) -> Self {
+        MinerError::Seed(e)
+    }
+}
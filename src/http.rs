@@ -0,0 +1,121 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crossbeam::atomic::AtomicCell;
+use crossbeam::channel::{bounded, Receiver, select, Sender, tick};
+use log::{debug, error, info};
+
+use derohe::rpc::client::Client;
+use derohe::rpc::daemon_rpc::{GetBlockTemplatePararms, GetBlockTemplateResult};
+
+use crate::common::definitions;
+use crate::dero::{Job, publish_job, Solution};
+use crate::error::MinerError;
+
+/// Mines through periodic `DERO.GetBlockTemplate` calls, for when websockets are not an option.
+#[derive(Debug, Clone)]
+pub struct TemplatePoller {
+    wallet_address: String,
+    daemon_rpc_address: String,
+    interval: Duration,
+    sender: Sender<MinerError>,
+    receiver: Receiver<MinerError>,
+    job: Arc<RwLock<Job>>,
+    latency: Arc<AtomicCell<Option<Duration>>>,
+    solutions: Receiver<Solution>,
+}
+
+impl TemplatePoller {
+    pub fn new(wallet_address: String, daemon_rpc_address: String, interval: Duration, job: Arc<RwLock<Job>>, solutions: Receiver<Solution>) -> Self {
+        let (sender, receiver) = bounded(10);
+        TemplatePoller {
+            wallet_address,
+            daemon_rpc_address,
+            interval,
+            sender,
+            receiver,
+            job,
+            latency: Arc::new(AtomicCell::new(None)),
+            solutions,
+        }
+    }
+
+    pub fn receiver(&self) -> Receiver<MinerError> {
+        self.receiver.clone()
+    }
+
+    /// Round trip time of the last template request.
+    pub fn latency(&self) -> Arc<AtomicCell<Option<Duration>>> {
+        self.latency.clone()
+    }
+
+    /// Polls until the daemon can't be reached and reports why, retrying is up to the receiver.
+    pub fn get_work(self) {
+        if let Err(e) = self.poll() {
+            let _ = self.sender.send(e);
+        }
+    }
+
+    fn poll(&self) -> Result<(), MinerError> {
+        let client = Client::new(&self.daemon_rpc_address);
+        info!("Polling {} for work", client.url());
+        let params = GetBlockTemplatePararms {
+            Wallet_Address: self.wallet_address.clone(),
+            Block: false,
+            Miner: definitions::MINER_NAME.to_string(),
+        };
+        let ticks = tick(self.interval);
+        let mut last: Option<GetBlockTemplateResult> = None;
+        loop {
+            select! {
+                recv(ticks) -> _ => {
+                    let start = Instant::now();
+                    let template = client.get_block_template(&params)?;
+                    self.latency.store(Some(start.elapsed()));
+                    if !is_new_work(last.as_ref(), &template) {
+                        continue;
+                    }
+                    debug!("{:#?}", template);
+                    last = Some(template.clone());
+                    if let Err(e) = publish_job(&self.job, template) {
+                        let _ = self.sender.send(e.into());
+                    }
+                }
+                recv(self.solutions) -> solution => {
+                    let solution = match solution {
+                        Ok(solution) => solution,
+                        Err(_) => return Ok(())
+                    };
+                    info!("Submitting miniblock for job {}", solution.job_id);
+                    match client.submit_block(&solution.params()) {
+                        Ok(result) => info!("Submitted miniblock, blid={}, mini={}, status={}", result.BLID, result.MiniBlock, result.Status),
+                        Err(e) => error!("Unable to submit miniblock: {}", e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_new_work(last: Option<&GetBlockTemplateResult>, template: &GetBlockTemplateResult) -> bool {
+    match last {
+        Some(last) => last.JobID != template.JobID || last.Height != template.Height,
+        None => true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use derohe::rpc::daemon_rpc::GetBlockTemplateResult;
+
+    use super::is_new_work;
+
+    #[test]
+    fn new_work() {
+        let template = GetBlockTemplateResult { JobID: String::from("1.0.notified"), Height: 10, ..Default::default() };
+        assert!(is_new_work(None, &template));
+        assert!(!is_new_work(Some(&template), &template.clone()));
+        assert!(is_new_work(Some(&template), &GetBlockTemplateResult { JobID: String::from("2.0.notified"), ..template.clone() }));
+        assert!(is_new_work(Some(&template), &GetBlockTemplateResult { Height: 11, ..template.clone() }));
+    }
+}
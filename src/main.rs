@@ -4,28 +4,39 @@ extern crate serde;
 
 mod common;
 mod dero;
+mod error;
+mod http;
+mod system;
 
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::{process, thread};
 use std::any::Any;
 use std::borrow::Borrow;
 use std::cmp::min;
-use std::error::Error;
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use affinity::{get_core_num, set_thread_affinity};
 
 use ctrlc;
-use clap::{ErrorKind, Parser, Subcommand, CommandFactory, FromArgMatches, Error as ClapError, Command};
+use clap::{ArgEnum, ArgGroup, ErrorKind, Parser, Subcommand, CommandFactory, Error as ClapError};
 use crossbeam::atomic::AtomicCell;
-use crossbeam::channel::{bounded, Receiver, select, tick};
+use crossbeam::channel::{after, bounded, never, Receiver, select, tick, unbounded};
 use crossbeam::sync::ShardedLock;
 use fern::colors::{Color, ColoredLevelConfig};
 use log::{error, info, trace, LevelFilter, debug, warn, log};
-use derohe::rpc::daemon_rpc;
 use derohe::rpc::daemon_rpc::GetBlockTemplateResult;
-use crate::dero::{Job, Miner, MinerError, WorkGatherer};
+use derohe::pow::backend::{self, Backend};
+use derohe::pow::memory::MemoryOptions;
+use derohe::rpc::address::{Address, Kind, Network};
+use derohe::transaction::RegistrationTx;
+use derohe::wallet::keys::{KeyError, SecretKey};
+use derohe::wallet::mnemonic;
+use crate::dero::{Job, MAX_CLOCK_SKEW_MILLIS, Miner, WorkGatherer, WorkSource};
+use crate::http::TemplatePoller;
+use crate::error::{MinerError, Recovery};
+use crate::system::CpuReport;
 
 /// A fictional versioning CLI
 #[derive(Parser)]
@@ -41,9 +52,41 @@ struct Cli {
     wallet_address: Option<String>,
     #[clap(short, long, global = true, default_value_t = num_cpus::get())]
     mining_threads: usize,
-    #[clap(short, long, global = true, default_value_t = String::from("127.0.0.1:10100"))]
-    /// Miner will connect to daemon RPC on this port.
-    daemon_rpc_address: String,
+    #[clap(short, long, global = true)]
+    /// Miner will connect to daemon RPC on this port, prefix with ws:// to skip TLS. Defaults to getwork on
+    /// 127.0.0.1:10100, or to JSON-RPC on 127.0.0.1:10102 with --protocol http.
+    daemon_rpc_address: Option<String>,
+    #[clap(long, global = true)]
+    /// Daemon JSON-RPC the wallet's network is checked on, defaults to the getwork host on port 10102.
+    /// The check is required to pass when this is given, otherwise a daemon that can't be reached is only warned about.
+    daemon_json_rpc_address: Option<String>,
+    #[clap(long, arg_enum, global = true, default_value = "websocket")]
+    /// How work is fetched, http polls DERO.GetBlockTemplate on the daemon JSON-RPC port.
+    protocol: Protocol,
+    #[clap(long, global = true, default_value_t = 1000)]
+    /// Milliseconds between block template requests in http mode.
+    poll_interval: u64,
+    #[clap(long, global = true, default_value_t = 60)]
+    /// Seconds without new work before mining pauses, 0 never pauses.
+    max_job_age: u64,
+    #[clap(long, global = true, default_value_t = Backend::default())]
+    /// Suffix sorter used for hashing, others are enabled with the Cargo feature of the same name.
+    pow_backend: Backend,
+    #[clap(long, global = true)]
+    /// Put each thread's hashing scratch on 2 MB huge pages, falls back to regular pages.
+    huge_pages: bool,
+    #[clap(long, global = true)]
+    /// Lock each thread's hashing scratch in RAM, needs a large enough RLIMIT_MEMLOCK.
+    lock_memory: bool,
+    #[clap(long, global = true)]
+    /// Pin each thread to a CPU and bind its hashing scratch to that CPU's NUMA node.
+    numa: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum Protocol {
+    Websocket,
+    Http,
 }
 
 #[derive(Subcommand)]
@@ -54,6 +97,63 @@ enum Commands {
     Benchmark {},
     /// Show version.
     Version {},
+    /// Generate a wallet secret key offline and print its address.
+    Keygen {
+        #[clap(long, default_value_t = Network::Mainnet)]
+        network: Network,
+        #[clap(long, parse(from_os_str))]
+        /// Write the secret key in hex to this new file, readable by the owner only, instead of printing it.
+        output: Option<PathBuf>,
+        #[clap(long, parse(from_os_str))]
+        /// Read a hex secret key from this file instead of generating one.
+        import: Option<PathBuf>,
+        #[clap(long, conflicts_with = "import")]
+        /// Restore the secret key from a 25 word seed read on stdin instead of generating one.
+        seed: bool,
+    },
+    /// Build the transaction registering a wallet's key on chain and print it in hex for submission.
+    #[clap(group(ArgGroup::new("key").required(true).args(&["import", "seed"])))]
+    Register {
+        #[clap(long, parse(from_os_str))]
+        /// Read the hex secret key from this file.
+        import: Option<PathBuf>,
+        #[clap(long)]
+        /// Read the secret key as a 25 word seed on stdin.
+        seed: bool,
+    },
+    /// Inspect, integrate or convert a DERO address.
+    Address {
+        #[clap(subcommand)]
+        command: AddressCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum AddressCommand {
+    /// Print the network, kind, public key and integrated arguments of an address.
+    Inspect {
+        address: Address,
+    },
+    /// Build an integrated address carrying a destination port, a comment or an amount.
+    #[clap(group(ArgGroup::new("arguments").required(true).multiple(true).args(&["port", "comment", "value"])))]
+    Integrate {
+        address: Address,
+        #[clap(long)]
+        /// Destination port the receiving service reads the payment with.
+        port: Option<u64>,
+        #[clap(long)]
+        comment: Option<String>,
+        #[clap(long)]
+        /// Amount to transfer in atomic units, 100000 per DERO.
+        value: Option<u64>,
+    },
+    /// Switch an address between the mainnet and testnet prefixes.
+    Convert {
+        address: Address,
+        #[clap(long)]
+        /// Network to convert to, defaults to the other one.
+        network: Option<Network>,
+    },
 }
 
 fn format_error<I: CommandFactory>(err: ClapError) -> ClapError {
@@ -86,7 +186,10 @@ fn main() {
                 ).exit();
             }
             info!("System will use {} thread(s) to mine.", cli.mining_threads);
-            start_miner(cli).unwrap()
+            if let Err(e) = start_miner(cli) {
+                error!("{}", e);
+                process::exit(e.exit_code());
+            }
         }
         Commands::Benchmark {} => {
             info!("Benchmarking")
@@ -94,7 +197,74 @@ fn main() {
         Commands::Version {} => {
             println!("{}", common::definitions::VERSION)
         }
+        Commands::Keygen { network, output, import, seed } => {
+            if let Err(e) = keygen(*network, output.as_deref(), import.as_deref(), *seed) {
+                error!("{}", e);
+                process::exit(e.exit_code());
+            }
+        }
+        Commands::Register { import, seed } => {
+            if let Err(e) = register(import.as_deref(), *seed, cli.mining_threads) {
+                error!("{}", e);
+                process::exit(e.exit_code());
+            }
+        }
+        Commands::Address { command } => match command {
+            AddressCommand::Inspect { address } => println!("{}", dero::address::describe(address)),
+            AddressCommand::Integrate { address, port, comment, value } => {
+                println!("{}", dero::address::integrate(address.clone(), *port, comment.clone(), *value))
+            }
+            AddressCommand::Convert { address, network } => {
+                if address.kind() == Kind::Proof {
+                    let mut cmd = <Cli as CommandFactory>::command();
+                    cmd.error(
+                        ErrorKind::InvalidValue,
+                        "proof addresses have the same prefix on every network",
+                    ).exit();
+                }
+                println!("{}", dero::address::convert(address.clone(), *network))
+            }
+        }
+    }
+}
+
+/// The secret key from a hex file or a seed on stdin, `None` if neither was asked for.
+fn read_secret(import: Option<&Path>, seed: bool) -> Result<Option<SecretKey>, MinerError> {
+    match import {
+        Some(path) => Ok(Some(SecretKey::read_from(path)?)),
+        None if seed => {
+            let mut phrase = String::new();
+            io::stdin().read_to_string(&mut phrase).map_err(KeyError::from)?;
+            Ok(Some(mnemonic::from_words(&phrase)?.0))
+        }
+        None => Ok(None),
+    }
+}
+
+fn keygen(network: Network, output: Option<&Path>, import: Option<&Path>, seed: bool) -> Result<(), MinerError> {
+    let secret = match read_secret(import, seed)? {
+        Some(secret) => secret,
+        None => SecretKey::generate(),
+    };
+    match output {
+        Some(path) => secret.write_to(path)?,
+        None => {
+            println!("secret key: {}", secret.to_hex());
+            println!("seed: {}", mnemonic::to_words(&secret, &mnemonic::ENGLISH));
+        }
     }
+    println!("address: {}", secret.address(network));
+    Ok(())
+}
+
+fn register(import: Option<&Path>, seed: bool, threads: usize) -> Result<(), MinerError> {
+    let secret = read_secret(import, seed)?.expect("clap requires a key; qed");
+    info!("Searching the registration proof of work for {} on {} thread(s)", secret.address(Network::Mainnet), threads);
+    let start = Instant::now();
+    let tx = RegistrationTx::build(&secret, threads);
+    info!("Found registration {} in {:?}", hex::encode(tx.hash()), start.elapsed());
+    println!("{}", hex::encode(tx.to_bytes()));
+    Ok(())
 }
 
 fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
@@ -106,40 +276,79 @@ fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
     Ok(receiver)
 }
 
-fn start_miner(cli: Cli) -> Result<(), Box<dyn Error>> {
+/// Where the Go daemon serves getwork by default.
+const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:10100";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How long the startup self-test measures single thread speed.
+const SELF_TEST_DURATION: Duration = Duration::from_millis(500);
+
+/// Checks the pow backend against known vectors and reports what the host offers before any thread mines.
+fn self_test(pow_backend: Backend) -> Result<(), MinerError> {
+    let report = CpuReport::detect();
+    info!("CPU {}", report);
+    let unused = report.unused_features();
+    if !unused.is_empty() {
+        warn!("CPU supports {} which this build doesn't target, rebuild with RUSTFLAGS=\"-C target-cpu=native\" for best speed", unused.join(", "));
+    }
+    backend::self_test(pow_backend)?;
+    let hash_rate = system::measure_hash_rate(pow_backend, SELF_TEST_DURATION);
+    info!("Self-test passed, pow backend={}, single thread speed={:.1} H/s", pow_backend, hash_rate);
+    Ok(())
+}
+
+fn start_miner(cli: Cli) -> Result<(), MinerError> {
     let ctrl_c_events = ctrl_channel()?;
     let ticks = tick(Duration::from_secs(10));
+    let stale_ticks = tick(Duration::from_secs(1));
+    let max_job_age = Duration::from_secs(cli.max_job_age);
     let wallet_address = cli.wallet_address.as_ref().unwrap().clone();
+    let wallet = Address::from_string(&wallet_address)?;
+    let daemon_address = cli.daemon_rpc_address.clone().unwrap_or_else(|| match cli.protocol {
+        Protocol::Websocket => String::from(DEFAULT_DAEMON_ADDRESS),
+        Protocol::Http => dero::address::json_rpc_address(DEFAULT_DAEMON_ADDRESS),
+    });
+    let json_rpc_address = match (&cli.daemon_json_rpc_address, cli.protocol) {
+        (Some(address), _) => address.clone(),
+        (None, Protocol::Http) => daemon_address.clone(),
+        (None, Protocol::Websocket) => dero::address::json_rpc_address(&daemon_address),
+    };
+    match dero::address::check_network(&wallet, &json_rpc_address) {
+        // daemons often serve JSON-RPC on localhost only, remote getwork miners can't always ask
+        Err(MinerError::NetworkUnknown { url, error }) if cli.daemon_json_rpc_address.is_none() => {
+            warn!("Unable to get the daemon's network from {}, the wallet address isn't checked against it, pass --daemon-json-rpc-address to require the check", url);
+            debug!("{}", error);
+        }
+        result => result?,
+    }
+    self_test(cli.pow_backend)?;
 
     let counter = Arc::new(AtomicCell::new(0 as i64));
-    let job = Arc::new(RwLock::new(Job {
-        ijob: daemon_rpc::GetBlockTemplateResult {
-            JobID: "".to_string(),
-            Blocktemplate_blob: "".to_string(),
-            Blockhashing_blob: "".to_string(),
-            Difficulty: "".to_string(),
-            Difficultyuint64: 0,
-            Height: 0,
-            Prev_Hash: "".to_string(),
-            EpochMilli: 0,
-            Blocks: 0,
-            MiniBlocks: 0,
-            LastError: "".to_string(),
-            Status: "".to_string(),
-        },
-        count: 0,
-    }));
-    let wg = WorkGatherer::new(wallet_address, cli.daemon_rpc_address, job.clone());
+    let job = Arc::new(RwLock::new(Job::default()));
+    let (solution_sender, solution_receiver) = unbounded();
+    let wg = match cli.protocol {
+        Protocol::Websocket => WorkSource::WebSocket(WorkGatherer::new(wallet_address, daemon_address, job.clone(), solution_receiver)),
+        Protocol::Http => WorkSource::Http(TemplatePoller::new(wallet_address, daemon_address, Duration::from_millis(cli.poll_interval), job.clone(), solution_receiver)),
+    };
     let wg_receiver = wg.receiver();
-    let jh = thread::spawn(move || wg.get_work());
+    let latency = wg.latency();
+    let gatherer = wg.clone();
+    thread::spawn(move || gatherer.get_work());
+    let mut reconnect = never();
     let cores: Vec<usize> = (0..get_core_num()).collect();
+    let memory = MemoryOptions { huge_pages: cli.huge_pages, lock: cli.lock_memory, numa: cli.numa };
     for i in 1..=cli.mining_threads.into() {
-        let miner = Miner::new(i, job.clone(), counter.clone());
+        let miner = Miner::new(i, job.clone(), counter.clone(), solution_sender.clone(), cli.pow_backend, memory);
         let item = i % cores.len();
         let bind_to = cores[item];
         debug!("Starting miner {}", i);
         thread::spawn(move || {
-            // set_thread_affinity(vec![bind_to]).unwrap();
+            // the scratch is bound to the node the thread allocates on, keep the thread there
+            if memory.numa {
+                if let Err(e) = set_thread_affinity(vec![bind_to]) {
+                    warn!("Unable to pin miner {} to CPU {}: {}", i, bind_to, e);
+                }
+            }
             miner.start()
         });
     }
@@ -161,12 +370,47 @@ fn start_miner(cli: Cli) -> Result<(), Box<dyn Error>> {
                 };
 
                 info!("Mining speed: {}", mining_speed_string);
+                if let Some(latency) = latency.load() {
+                    info!("Daemon latency: {:?}", latency);
+                }
+                let clock_skew = job.read().unwrap().clock_skew;
+                if clock_skew.abs() > MAX_CLOCK_SKEW_MILLIS {
+                    warn!("Local clock is {} ms off the daemon", clock_skew);
+                }
+            }
+            recv(stale_ticks) -> _ => {
+                if max_job_age.is_zero() {
+                    continue;
+                }
+                let stale = {
+                    let job = job.read().unwrap();
+                    job.count > 0 && !job.paused && job.age() > max_job_age
+                };
+                if stale {
+                    let mut job = job.write().unwrap();
+                    warn!("No new work for {:?}, pausing until the daemon sends a new job", job.age());
+                    job.pause();
+                }
             }
             recv(wg_receiver) -> val => {
-                match val.unwrap() {
-                    MinerError::WebSocketError(val) => error!("{}",val)
+                let err = val.unwrap();
+                match err.recovery() {
+                    Recovery::Retry => {
+                        error!("{}, reconnecting in {:?}", err, RECONNECT_DELAY);
+                        reconnect = after(RECONNECT_DELAY);
+                    }
+                    Recovery::Pause => {
+                        warn!("{}, pausing until the next job", err);
+                        job.write().unwrap().pause();
+                    }
+                    Recovery::Exit(_) => return Err(err)
                 }
             }
+            recv(reconnect) -> _ => {
+                let gatherer = wg.clone();
+                thread::spawn(move || gatherer.get_work());
+                reconnect = never();
+            }
             recv(ctrl_c_events) -> _ => {
                 info!("Goodbye!");
                 break;
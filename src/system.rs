@@ -0,0 +1,123 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use derohe::pow::astrobwt::Pow16Context;
+use derohe::pow::backend::Backend;
+use derohe::pow::salsa20;
+
+/// What the host offers the miner, logged once before mining starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuReport {
+    pub sse2: bool,
+    pub avx2: bool,
+    pub sha: bool,
+    pub bmi2: bool,
+    pub physical_cores: usize,
+    pub logical_cores: usize,
+    /// Total memory in bytes, `None` when the OS doesn't tell us.
+    pub memory: Option<u64>,
+}
+
+impl CpuReport {
+    pub fn detect() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let (sse2, avx2, sha, bmi2) = (
+            is_x86_feature_detected!("sse2"),
+            is_x86_feature_detected!("avx2"),
+            is_x86_feature_detected!("sha"),
+            is_x86_feature_detected!("bmi2"),
+        );
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let (sse2, avx2, sha, bmi2) = (false, false, false, false);
+        CpuReport {
+            sse2,
+            avx2,
+            sha,
+            bmi2,
+            physical_cores: num_cpus::get_physical(),
+            logical_cores: num_cpus::get(),
+            memory: total_memory(),
+        }
+    }
+
+    /// Features the CPU has that this build wasn't compiled for and no pow stage picks at runtime.
+    /// SHA-NI is left out, it speeds up SHA-2 but pow16 hashes with SHA-3.
+    pub fn unused_features(&self) -> Vec<&'static str> {
+        let compiled = [
+            ("sse2", self.sse2, cfg!(target_feature = "sse2")),
+            ("avx2", self.avx2, cfg!(target_feature = "avx2")),
+            ("bmi2", self.bmi2, cfg!(target_feature = "bmi2")),
+        ];
+        compiled.iter()
+            .filter(|(name, cpu, build)| *cpu && !*build && !salsa20::RUNTIME_FEATURES.contains(name))
+            .map(|(name, _, _)| *name)
+            .collect()
+    }
+}
+
+impl fmt::Display for CpuReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |name: &str, present: bool| if present { name.to_string() } else { format!("-{}", name) };
+        write!(f, "features={} {} {} {}, cores={}, threads={}", flag("sse2", self.sse2), flag("avx2", self.avx2),
+               flag("sha", self.sha), flag("bmi2", self.bmi2), self.physical_cores, self.logical_cores)?;
+        match self.memory {
+            Some(bytes) => write!(f, ", memory={:.1} GiB", bytes as f64 / (1u64 << 30) as f64),
+            None => write!(f, ", memory=unknown")
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn total_memory() -> Option<u64> {
+    parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn total_memory() -> Option<u64> {
+    None
+}
+
+/// Reads `MemTotal` out of /proc/meminfo, which is given in KiB.
+fn parse_meminfo(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Hashes on the current thread for about `duration`, returns hashes per second.
+pub fn measure_hash_rate(backend: Backend, duration: Duration) -> f64 {
+    let mut context = Pow16Context::with_backend(backend);
+    let mut input = [0u8; derohe::block::MINIBLOCK_SIZE];
+    let start = Instant::now();
+    let mut hashes = 0u32;
+    while hashes == 0 || start.elapsed() < duration {
+        input[..4].copy_from_slice(&hashes.to_le_bytes());
+        context.hash(&input);
+        hashes += 1;
+    }
+    hashes as f64 / start.elapsed().as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_meminfo, CpuReport};
+
+    #[test]
+    fn meminfo() {
+        let meminfo = "MemTotal:       32780604 kB\nMemFree:         1130084 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(32780604 * 1024));
+        assert_eq!(parse_meminfo("MemFree: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn unused_features() {
+        let report = CpuReport { sse2: false, avx2: false, sha: false, bmi2: false, physical_cores: 1, logical_cores: 1, memory: None };
+        assert!(report.unused_features().is_empty());
+        // salsa20 dispatches avx2 at runtime, a build without it still uses it
+        let report = CpuReport { avx2: true, sha: true, ..report };
+        assert!(report.unused_features().is_empty());
+        assert_eq!(report.to_string(), "features=-sse2 avx2 sha -bmi2, cores=1, threads=1, memory=unknown");
+        let report = CpuReport { bmi2: true, ..report };
+        assert_eq!(report.unused_features(), if cfg!(target_feature = "bmi2") { vec![] } else { vec!["bmi2"] });
+    }
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mock_daemon::{Config, Event, MockDaemon};
+
+const WALLET_ADDRESS: &str = "dero1qyp5p7r2tqad8fvylrp4xp9l9q499kcpglvq4frw0pd4qtrtsa5d5qqx7tpmn";
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn miner(daemon: &MockDaemon) -> Child {
+    miner_checking(daemon, &daemon.rpc_url())
+}
+
+fn miner_checking(daemon: &MockDaemon, json_rpc_address: &str) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_rustic-miner"))
+        .args(["mine", "-w", WALLET_ADDRESS, "-m", "1", "-d", &daemon.url(), "--daemon-json-rpc-address", json_rpc_address])
+        .current_dir(env::temp_dir())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+fn wait_exit(child: &mut Child, timeout: Duration) -> Option<i32> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status.code();
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    None
+}
+
+fn stop(child: &mut Child) {
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn submits_valid_miniblocks() {
+    let daemon = MockDaemon::start("127.0.0.1:0", Config::default()).unwrap();
+    let mut child = miner(&daemon);
+    let submitted = daemon.wait_for(TIMEOUT, |d| d.submissions().iter().any(|s| s.accepted()));
+    stop(&mut child);
+    assert!(submitted, "no accepted miniblock, got {:?}", daemon.submissions());
+    assert!(daemon.submissions().iter().all(|s| s.wallet_address == WALLET_ADDRESS));
+}
+
+#[test]
+fn survives_bad_payload_and_disconnect() {
+    let daemon = MockDaemon::start("127.0.0.1:0", Config {
+        script: vec![
+            Event::Raw { text: String::from("{not json") },
+            Event::Wait { millis: 200 },
+            Event::Disconnect,
+        ],
+        ..Config::default()
+    }).unwrap();
+    let mut child = miner(&daemon);
+    let submitted = daemon.wait_for(TIMEOUT, |d| d.connections() >= 2 && d.submissions().iter().any(|s| s.accepted()));
+    stop(&mut child);
+    assert!(submitted, "miner didn't reconnect and submit, connections={}", daemon.connections());
+}
+
+#[test]
+fn exits_when_address_is_rejected() {
+    let daemon = MockDaemon::start("127.0.0.1:0", Config {
+        script: vec![
+            Event::Close { code: 1008, reason: String::from("address not registered") },
+        ],
+        ..Config::default()
+    }).unwrap();
+    let mut child = miner(&daemon);
+    let code = wait_exit(&mut child, TIMEOUT);
+    if code.is_none() {
+        stop(&mut child);
+    }
+    assert_eq!(code, Some(6));
+}
+
+#[test]
+fn exits_on_network_mismatch() {
+    let daemon = MockDaemon::start("127.0.0.1:0", Config { network: String::from("Testnet"), ..Config::default() }).unwrap();
+    let mut child = miner(&daemon);
+    let code = wait_exit(&mut child, TIMEOUT);
+    if code.is_none() {
+        stop(&mut child);
+    }
+    assert_eq!(code, Some(8));
+    assert_eq!(daemon.connections(), 0);
+}
+
+#[test]
+fn exits_when_network_check_is_unreachable() {
+    let daemon = MockDaemon::start("127.0.0.1:0", Config::default()).unwrap();
+    // the getwork port doesn't answer JSON-RPC
+    let mut child = miner_checking(&daemon, &format!("http://{}", daemon.address()));
+    let code = wait_exit(&mut child, TIMEOUT);
+    if code.is_none() {
+        stop(&mut child);
+    }
+    assert_eq!(code, Some(8));
+    assert_eq!(daemon.connections(), 0);
+}